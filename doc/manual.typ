@@ -125,6 +125,47 @@ d'erreurs où l'ID attendu n'est pas le bon
 ])
 
 
+#descr("Obtenir la carte de la galaxie du joueur", "player_map", "/player/map", "get_player_map", [
+  Retourne tout ce que le joueur a découvert de la galaxie, à l'aide de ses scans
+  (voir @station_scan et @ship_scan) ou des voyages de ses vaisseaux:
+  - `sectors`: Les secteurs de la galaxie connus
+  - `planets`: Les planètes connues, avec les dernières informations scannées
+  - `stations`: Les stations connues, avec les dernières informations scannées
+], none, footer: [
+  Être le premier joueur à découvrir un secteur de la galaxie rapporte des points
+  de score
+])
+
+#descr("Obtenir l'historique de son score", "player_scores", "/player/scores", "get_player_scores", [
+  Le score d'un joueur est sa valeur nette (argent, prix de revente de ses vaisseaux,
+  et resources dans ses cargos au prix actuel du marché), plus les points bonus gagnés.
+
+  Il est calculé régulièrement, cette requête retourne les derniers calculs avec
+  leur détail: `money`, `fleet`, `cargo`, `trading` (profit net des échanges sur le
+  marché), `bonus`, `nb_ships`, `net_worth` et `total`.
+], none)
+
+#descr("Obtenir ses succès", "player_achievements", "/player/achievements", "get_player_achievements", [
+  Certaines étapes du jeu débloquent des succès (premier vaisseau, première vente,
+  premier or extrait, ...), qui peuvent rapporter de l'argent.
+
+  Retourne les succès débloqués par le joueur (`unlocked`), et la liste de tous
+  les succès existants avec leur description et leur récompense (`all`).
+], none, footer: [
+  Un évènement `AchievementUnlocked` est ajouté aux logs du joueur (voir @syslog)
+  lorsqu'un succès est débloqué
+])
+
+#descr("Obtenir le classement", "leaderboard", "/leaderboard", "get_leaderboard", [
+  Retourne le classement des joueurs encore en jeu (`ranking`), avec leur score et
+  sa progression depuis le calcul précédent (`delta`).
+
+  Contient également les classements par catégorie: `richest` (argent),
+  `best-trader` (profit sur le marché) et `biggest-fleet` (nombre de vaisseaux).
+
+  Ne nécessite pas de clé de joueur pour être appelée.
+], none)
+
 #pagebreak()
 = Les vaisseaux <ship>
 
@@ -138,16 +179,19 @@ d'erreurs où l'ID attendu n'est pas le bon
 - `fuel_tank_capacity`: Contenance du réservoir de carburant
 - `hull_decay_capacity`: Quantité d'usure que la coque peut subir avant destruction
 - `modules`: Tous les modules du vaisseau et leur ID
+- `module_slots`: Nombre d'emplacements de modules du vaisseau
 
 *Cargo*
 - `cargo.usage`: Volume utilisé par les resources à bord
 - `cargo.capacity`: Volume total que peut contenir le cargo
 - `cargo.resources`: Pour chaque resource contenue, quelle quantité est en stock
+- `extracted`: Quantités de chaque resource extraites depuis le début de la dernière extraction
 
 *État*
 - `state`: Commande actuelle du vaisseau (en vol, extraction, inactif...)
 - `fuel_tank`: Niveau de carburant restant, à `0` le vaisseau s'immobilisera
 - `hull_decay`: Usure de la coque, à `hull_decay_capacity`, le vaisseau se détruira
+  (plus la coque est usée, plus le vaisseau est lent: usé à 70%, vitesse réduite de 35%)
 - `stats`: Performances du vaisseau, calculées à partir des caractéristiques du vaisseau
 
 *Équipage*
@@ -166,6 +210,16 @@ d'erreurs où l'ID attendu n'est pas le bon
 ], none, footer: [
   Les données reçues, en plus des caractéristiques du vaisseau en question, contient le prix à
   payer pour ce vaisseau (clé `price`).
+
+  Chaque vaisseau appartient à une classe de coque (`hull_class`: `Shuttle`, `Hauler`,
+  `Prospector`, `Explorer` ou `Frigate`) qui détermine ses caractéristiques et les modules
+  déjà installés, et possède une rareté (`rarity`: `Common`, `Uncommon`, `Rare` ou `Legendary`)
+  qui améliore ses performances.
+
+  Un vaisseau `Shuttle` bon marché est toujours proposé. Les offres sont renouvelées
+  toutes les 10 minutes de jeu. Certaines stations proposent des vaisseaux plus rares que
+  d'autres, et le rang du marchand de la station améliore la qualité des offres
+  (clé `shipyard-quality` de l'économie de la station).
 ])
 
 
@@ -173,13 +227,34 @@ d'erreurs où l'ID attendu n'est pas le bon
   Achète le vaisseau d'ID `ship_id` sur la station `station_id`
 ], "le prix est au dessus de vos moyens financiers",
   footer: [
-    Le nouveau vaisseau aura pour position celle de la station, avec un plein de carburant
-    et une coque toute neuve.
+    Le nouveau vaisseau aura pour position celle de la station, avec un plein de carburant.
+    Un vaisseau revendu par un joueur garde l'usure de sa coque, et son prix en tient compte.
 
     *Attention*: Sans équipage, le vaisseau ne peut pas fonctionner (voir @crew)
   ]
 )
 
+#descr("Vendre un vaisseau", "sell_ship", "/station/{station_id}/shipyard/sell/{ship_id}", "shipyard_sell_ship", [
+  Revend le vaisseau d'ID `ship_id` au chantier naval de la station `station_id`, qui le
+  remet en vente. Retourne le prix obtenu (clé `price`).
+], "le vaisseau n'est pas à quai sur la station, est occupé, ou sa soute n'est pas vide",
+  footer: [
+    Le prix de revente est une part du prix de vente du vaisseau sur cette station, et diminue
+    avec l'usure de sa coque. L'équipage du vaisseau retourne dans l'équipage inactif de la station.
+  ]
+)
+
+#descr("Démanteler un vaisseau", "scrap_ship", "/station/{station_id}/shipyard/scrap/{ship_id}", "shipyard_scrap_ship", [
+  Démantèle le vaisseau d'ID `ship_id` et place les ressources récupérées dans la soute de la
+  station `station_id` (clé `recovered`).
+], "le vaisseau n'est pas à quai sur la station ou est occupé",
+  footer: [
+    Le démantèlement rend des plaques de coque, du fer, du cuivre pour les modules, le carburant
+    restant ainsi que le contenu de la soute. Ce qui ne rentre pas dans la soute de la station
+    est perdu. L'équipage du vaisseau retourne dans l'équipage inactif de la station.
+  ]
+)
+
 #descr("Lister les modules de vaisseau à l'achat", "list_shop_ship_module",
   "/station/{station_id}/shop/modules", "list_shop_ship_module",
   [ Liste tous les modules de vaisseaux disponibles à l'achat dans cette station,
@@ -193,8 +268,35 @@ d'erreurs où l'ID attendu n'est pas le bon
     Types de module possible:
     - `Miner`: Permet d'extraire des resources depuis les planètes solides
     - `GasSucker`: Permet d'extraire du gaz depuis les planète gaseuses
+    - `Scanner`: Permet de scanner les alentours du vaisseau (voir @ship_scan)
+    - `CargoPod`: Augmente la capacité de la soute du vaisseau
+    - `FuelTank`: Augmente la capacité du réservoir de carburant
+    - `RepairDrone`: Répare la coque pendant les vols, en consommant les plaques de coque
+      (`HullPlate`) présentes dans la soute du vaisseau
+    - `Afterburner`: Augmente la vitesse du vaisseau, mais augmente encore plus sa consommation
+      de carburant
+
+    Seuls les modules `Miner`, `GasSucker` et `Scanner` ont besoin d'un opérateur. L'effet des
+    autres modules augmente avec leur rang.
+
+    Chaque vaisseau possède un nombre limité d'emplacements de modules (clé `module_slots`),
+    qui dépend de sa classe de coque et de sa rareté. Les modules `Miner`, `GasSucker` et
+    `Afterburner` occupent 2 emplacements, les autres un seul.
+    L'ID d'un module retiré n'est jamais réattribué sur ce vaisseau.
   ],
-  "le type de module n'est pas reconnu, si le vaisseau n'est pas à la station, le prix est trop élevé"
+  "le type de module n'est pas reconnu, si le vaisseau n'est pas à la station, le prix est trop élevé, ou s'il n'y a plus assez d'emplacements libres"
+)
+
+#descr("Vendre un module de vaisseau", "sell_ship_module",
+  "/station/{station_id}/shop/modules/{ship_id}/sell/{module}", "sell_ship_module", [
+    Retire le module d'ID `module` du vaisseau `ship_id` et le revend à la station.
+    Retourne le prix obtenu (clé `price`).
+  ], "le vaisseau n'est pas à quai sur la station, est occupé, ou le module est une soute
+  additionnelle dont le retrait ne laisserait pas assez de place pour le contenu de la soute",
+  footer: [
+    Le prix de revente est la moitié de la valeur du module et de ses rangs. L'opérateur du
+    module retourne dans l'équipage inactif de la station.
+  ]
 )
 
 #descr("Lister les améliorations de modules", "list_ship_module_upgrades",
@@ -203,7 +305,10 @@ d'erreurs où l'ID attendu n'est pas le bon
     le prix à payer pour augmenter leur rang.
 
     Chaque rang de module booste son efficacité, et permet d'extraire des resources
-    nécessitant un rang élevé
+    nécessitant un rang élevé. Chaque gain de rang apporte moins que le précédent.
+
+    Le rang maximal (clé `max-rank`) est de 20 pour les modules d'extraction, et de 10 pour
+    les scanners
   ], "le vaisseau n'est pas à la station"
 )
 
@@ -211,7 +316,7 @@ d'erreurs où l'ID attendu n'est pas le bon
   "/station/{station_id}/shop/modules/{ship_id}/upgrade/{module}",
   "buy_ship_module_upgrade", [
     Achète une amélioration de l'un des modules installés sur le vaisseau
-  ], "le prix est élevé pour vos moyens, ou le vaisseau n'est pas à la station"
+  ], "le prix est élevé pour vos moyens, le vaisseau n'est pas à la station, ou le module a atteint son rang maximal"
 )
 
 #descr("Extraire des resources", "start_extraction",
@@ -245,12 +350,74 @@ d'erreurs où l'ID attendu n'est pas le bon
   - `Ozone`, plus cher mais plus rare et difficile à extraire
 ])
 
+#descr("Scanner depuis un vaisseau", "ship_scan",
+  "/ship/{ship_id}/scan", "ship_scan", [
+    Scanne les alentours de la position du vaisseau, à l'aide de son meilleur module
+    `Scanner` ayant un opérateur assigné.
+
+    Les secteurs de la galaxie à portée du scanner qui n'avaient encore jamais été
+    explorés sont découverts par ce scan, avec les planètes qu'ils contiennent.
+
+    Le rayon du scan et le niveau de détail obtenu dépendent du rang du module
+    (voir @station_scan)
+  ], "le vaisseau n'a aucun module `Scanner` avec un opérateur assigné"
+)
+
 #descr("Arrêter l'extraction", "stop_extraction",
   "/ship/{ship_id}/extraction/stop", "stop_extraction", [
     Arrête l'extraction en cours, restaure l'état du vaisseau à `Idle` (inactif)
   ], "le vaisseau n'était pas en train d'extraire"
 )
 
+#descr("Faire le plein depuis le cargo du vaisseau", "ship_refuel",
+  "/ship/{ship_id}/refuel", "refuel_ship_from_cargo", [
+    Remplit le réservoir du vaisseau avec le carburant (`Fuel`) présent dans son propre cargo,
+    où qu'il soit dans l'espace. Retourne la quantité de carburant ajoutée (clé `added-fuel`).
+  ], "le vaisseau est occupé ou n'a pas de carburant dans son cargo"
+)
+
+#descr("Réparer la coque depuis le cargo du vaisseau", "ship_repair",
+  "/ship/{ship_id}/repair/start", "start_ship_repair", [
+    Les opérateurs à bord du vaisseau réparent sa coque à l'aide des plaques de coque
+    (`HullPlate`) présentes dans son cargo. Le `state` du vaisseau devient `Repairing`, et
+    la vitesse de réparation par seconde est retournée (clé `rate`).
+
+    La réparation s'arrête d'elle-même lorsque la coque est réparée, ou qu'il n'y a plus de
+    plaques de coque dans le cargo. La vitesse dépend du nombre d'opérateurs à bord, de leur
+    rang, de leur expérience et de leur moral.
+  ], "le vaisseau est déjà occupé, n'a pas de plaques de coque dans son cargo, ou n'a aucun opérateur à bord"
+)
+
+#descr("Arrêter la réparation", "stop_ship_repair",
+  "/ship/{ship_id}/repair/stop", "stop_ship_repair", [
+    Arrête la réparation en cours, restaure l'état du vaisseau à `Idle` (inactif)
+  ], "le vaisseau n'était pas en train de se réparer"
+)
+
+#descr("Obtenir le prix d'un remorquage", "tow_cost",
+  "/ship/{ship_id}/tow/cost", "get_tow_cost", [
+    Retourne l'offre du service de remorquage pour ce vaisseau: la station la plus proche
+    que vous possédez (clé `station`) et ses coordonnées (clé `destination`), le prix
+    (clé `price`) et le temps en secondes avant que le vaisseau n'y soit amené (clé `remaining`).
+
+    Seul un vaisseau bloqué peut être remorqué: son réservoir est vide, ou il ne peut pas
+    rejoindre cette station par lui-même (pas de pilote, pas assez de carburant ou de coque).
+  ], "le vaisseau est déjà sur l'une de vos stations, ou peut rejoindre la station par lui-même"
+)
+
+#descr("Remorquer un vaisseau", "tow",
+  "/ship/{ship_id}/tow", "call_tow", [
+    Fait appel au service de remorquage, qui ramène le vaisseau à la station la plus proche
+    que vous possédez (voir @tow_cost). Le `state` du vaisseau devient `Towed` jusqu'à son
+    arrivée, et vous ne pouvez plus le commander.
+  ], "le vaisseau est occupé, est déjà sur l'une de vos stations, peut rejoindre la station par lui-même, ou le prix est au dessus de vos moyens financiers",
+  footer: [
+    Un vaisseau dont le réservoir est vide s'immobilise en plein espace. Pour le secourir, vous
+    pouvez faire appel au remorquage, ou lui envoyer un autre vaisseau pour lui transférer
+    du carburant (voir @transfer_fuel).
+  ]
+)
+
 #pagebreak()
 = Stations <stations>
 
@@ -270,6 +437,17 @@ d'erreurs où l'ID attendu n'est pas le bon
   - `crew`: Équipage de cette station (voir @crew)
   - `trader`: ID du membre d'équipage assigné en tant que Trader
 
+  *Économie*
+
+  Chaque station a sa propre économie, les prix y sont différents des autres stations:
+  - `economy.price-rates`: Multiplicateur des prix des vaisseaux (`Ship`), des modules
+    et de leurs améliorations (`Module`) et des améliorations de vaisseau (`Upgrade`)
+  - `economy.resource-prices`: Prix des resources lors d'un achat / d'une vente sur le
+    marché depuis cette station
+  - `economy.demand`: Demande actuelle pour chaque type d'équipement, varie avec le temps
+  - `economy.recent-purchases`: Argent dépensé récemment dans la station pour chaque
+    type d'équipement, plus il est élevé plus les prix y augmentent
+
 #descr("Obtenir le status de la station", "station_status",
   "/station/{station_id}", "get_station_status", [
     Retourne les données de la station (voir @stationdata pour le détail)
@@ -279,6 +457,8 @@ d'erreurs où l'ID attendu n'est pas le bon
     - `idle_crew`
     - `cargo`
     - `trader`
+    - `scanner_rank`
+    - `economy`
 ], none)
 
 #descr("Faire le plein de carburant", "refuel", "/station/{station_id}/refuel/{ship_id}",
@@ -316,6 +496,34 @@ d'erreurs où l'ID attendu n'est pas le bon
     L'appel à cet endpoint retournera la quantité de resource qui a été déchargée
 ], "le vaisseau n'est pas dans une station")
 
+#descr("Transférer du carburant entre vaisseaux", "transfer_fuel",
+  "/ship/{ship_id}/transfer/{to_id}/fuel/{amnt}", "transfer_ship_fuel", [
+    Transfère une quantité `amnt` de carburant du réservoir du vaisseau `ship_id` vers celui
+    du vaisseau `to_id`.
+
+    Transfère au maximum ce que contient le réservoir du premier vaisseau, et ce que peut
+    accueillir celui du second. Retourne la quantité transférée (clé `transferred`).
+], "les vaisseaux ne sont pas à la même position, l'un d'eux est en vol, ou `amnt` n'est pas un nombre positif",
+  footer: [
+    Les deux vaisseaux doivent se trouver aux mêmes coordonnées, mais pas forcément sur une
+    station: un vaisseau ravitailleur peut rejoindre un autre vaisseau en plein espace.
+  ]
+)
+
+#descr("Transférer des resources entre vaisseaux", "transfer_cargo",
+  "/ship/{ship_id}/transfer/{to_id}/cargo/{resource}/{amnt}", "transfer_ship_cargo", [
+    Transfère une quantité `amnt` de la resource `resource` (par exemple des plaques de coque
+    `HullPlate` ou du minerai) du cargo du vaisseau `ship_id` vers celui du vaisseau `to_id`.
+
+    Ce qui ne rentre pas dans le cargo du second vaisseau reste dans celui du premier.
+    Retourne la quantité transférée (clé `transferred`).
+], "les vaisseaux ne sont pas à la même position, l'un d'eux est en vol, ou `amnt` n'est pas un nombre positif",
+  footer: [
+    Un vaisseau en cours d'extraction peut donner ou recevoir des resources, ce qui permet à un
+    vaisseau de transport de récupérer le minerai d'un vaisseau minier sans interrompre son travail.
+  ]
+)
+
 #descr("Lister le prix des améliorations de vaisseau", "upgr_ship_price", "/station/{station_id}/shipyard/upgrade", "list_ship_upgrades", [
   Retourne la liste de toutes les améliorations possibles sur les vaisseaux sur cette
   station, ainsi que leur prix.
@@ -333,6 +541,7 @@ d'erreurs où l'ID attendu n'est pas le bon
     Retourne le prix à payer pour chaque amélioration de la station:
     - `cargo-expansion`: Prix par extension de cargo (voir @buy_cargo_exp)
     - `trader-upgrade`: Prix pour augmenter le rang du trader (voir @upgrade_trader)
+    - `scanner-upgrade`: Prix pour augmenter le rang du scanner (voir @buy_scanner_upgr)
 
     *Attention* La fonction de `ApiClient` nécessite de passer un paramètre `key`
     correspondant au type d'amélioration à avoir
@@ -348,10 +557,26 @@ d'erreurs où l'ID attendu n'est pas le bon
   ], "vous n'avez pas assez d'argent",
 )
 
+#descr("Acheter une amélioration du scanner", "buy_scanner_upgr",
+  "/station/{station_id}/shop/scanner/upgrade", "buy_scanner_upgrade", [
+    Augmente le rang du scanner de la station (voir @station_scan).
+
+    Le prix augmentera à chaque rang acheté, jusqu'au rang maximal de 10
+  ], "vous n'avez pas assez d'argent ou le scanner a atteint son rang maximal",
+)
+
 #descr("Scanner les planètes aux alentours", "station_scan", "/station/{station_id}/scan", "station_scan", [
-  Scanne tous les objets dans ce secteur de la galaxie, et retourne leur liste:
+  Scanne tous les objets dans une sphère autour de la station, et retourne leur liste:
   - Planètes
   - Stations
+  - Vaisseaux en vol (à partir du rang 3)
+
+  Le rayon de la sphère augmente avec le rang du scanner (`scanner_rank`), ainsi que
+  le niveau de détail obtenu:
+  - Rang 2: Densité des resources des planètes, propriétaire des stations
+  - Rang 3: Vaisseaux en vol, et leur propriétaire
+  - Rang 4: Équipage des stations
+  - Rang 5: Cargo des stations, destination des vaisseaux en vol
 
   Peut être utilisé pour obtenir la position d'une planète à aller exploiter, ses
   caractéristiques (si elle est _solide_ ou _gazeuse_, voir @start_extraction)
@@ -368,6 +593,24 @@ d'erreurs où l'ID attendu n'est pas le bon
   - `CrewMemberType::Operator`: Opérateur de module (voir @assign_operator et @shipdata)
   - `CrewMemberType::Trader`: Trader sur la station (voir @assign_trader et @stationdata)
   - `CrewMemberType::Soldier`: Soldat (développement en cours)
+- `xp`: Expérience de l'équipier, gagnée en travaillant
+  - Un pilote gagne la distance parcourue à la fin de chaque vol
+  - Un opérateur gagne le volume de resources extraites
+  - Un trader gagne la quantité de resources achetées ou vendues
+- `morale`: Moral de l'équipier, entre 0 et 1
+- `traits`: Traits de caractère de l'équipier (voir @crew_candidates)
+- `wage_rate`: Multiplicateur sur le salaire demandé par l'équipier
+
+L'expérience améliore les performances de l'équipier jusqu'à 25%, de moins en moins vite.
+Un équipier sans aucun moral ne travaille plus qu'à 70% de ses capacités.
+
+Le moral baisse lorsqu'il vous reste moins d'une minute de salaires à payer, et remonte
+lentement sinon. Le moral de l'équipage d'un vaisseau en vol ne change pas avant son
+arrivée. Arriver au bout d'un vol avec une coque usée à plus de 75% ou moins de 10% de
+carburant fait aussi baisser le moral de tout l'équipage du vaisseau.
+
+Les performances d'un pilote changent la vitesse et la consommation de carburant du vaisseau,
+celles d'un opérateur la vitesse d'extraction, et celles d'un trader les frais de transaction.
 
 #descr("Engager un membre d'équipage", "hire_crew", "/station/{station_id}/crew/hire/{crew_type}",  "hire_crew", [
   Engager un nouveau member d'équipage, qui rejoindra la station en status *inactif* (voir @station_status)
@@ -378,10 +621,35 @@ d'erreurs où l'ID attendu n'est pas le bon
   - `trader`: Permet d'acheter ou vendre des resources dans une station (voir @sell_resources)
 
   Chaque membre d'équipage pourra ensuite être améliorer (voir @upgrade_crew)
-], "le type de member n'est pas reconnu", footer: [
-  L'ID du membre de l'équipage sera retourné.
+], "le type de member n'est pas reconnu ou vous n'avez pas assez d'argent", footer: [
+  L'ID du membre de l'équipage sera retourné, ainsi que le coût de l'embauche (clé `cost`),
+  égal à 5 minutes de son salaire.
+])
+
+#descr("Lister les candidats", "crew_candidates", "/station/{station_id}/crew/candidates", "list_crew_candidates", [
+  Liste les candidats qui attendent d'être embauchés sur cette station, avec pour chacun
+  son type, son rang de départ, ses traits de caractère, son salaire et le coût de son embauche.
+
+  Les traits possibles sont:
+  - `Frugal` (pilote): -10% de consommation de carburant
+  - `Reckless` (pilote): +10% de vitesse, +15% de consommation de carburant
+  - `Meticulous` (opérateur): +10% de vitesse d'extraction
+  - `Clumsy` (opérateur): -15% de vitesse d'extraction
+  - `Haggler` (trader): -10% de frais de transaction
+  - `Careless` (trader): +15% de frais de transaction
+  - `Loyal`: Perd son moral deux fois moins vite
+  - `Greedy`: +20% de salaire
+], none, footer: [
+  Les candidats sont renouvelés toutes les 5 minutes de jeu. Les candidats avec de bons traits
+  demandent un salaire plus élevé.
 ])
 
+#descr("Embaucher un candidat", "hire_candidate", "/station/{station_id}/crew/candidates/hire/{crew_id}", "hire_crew_candidate", [
+  Embauche le candidat `crew_id`, qui rejoint l'équipage inactif de la station avec son
+  rang et ses traits. Retourne l'identifiant du nouveau membre d'équipage (clé `id`), qui
+  peut être différent de celui du candidat, et le coût de l'embauche (clé `cost`)
+], "le candidat n'existe pas sur cette station ou vous n'avez pas assez d'argent")
+
 #descr("Assigner un trader", "assign_trader", "/station/{station_id}/crew/assign/{crew_id}/trading", "assign_trader", [
   Assigne un trader à une station, permet alors d'utiliser les fonctionalités d'achat
   et de vente de resources (voir @sell_resources et @buy_resources)
@@ -403,6 +671,30 @@ d'erreurs où l'ID attendu n'est pas le bon
   du rang de l'opérateur
 ], "le vaisseau n'est pas sur la station, un opérateur est déjà assigné à ce module, le membre d'équipage n'est pas inactif ou n'est pas un opérateur")
 
+#descr("Retirer le trader", "unassign_trader", "/station/{station_id}/crew/unassign/trading", "unassign_trader", [
+  Retire le trader assigné à la station, qui retourne dans l'équipage inactif de la station.
+  Retourne son ID (clé `id`)
+], "aucun trader n'est assigné à cette station")
+
+#descr("Débarquer un membre d'équipage", "unassign_crew", "/station/{station_id}/crew/unassign/{crew_id}/{ship_id}", "unassign_crew", [
+  Fait descendre le membre d'équipage `crew_id` du vaisseau `ship_id`, il retourne dans
+  l'équipage inactif de la station et libère son poste (pilote ou opérateur d'un module).
+], "le vaisseau n'est pas sur la station, est occupé, ou le membre d'équipage n'est pas à bord")
+
+#descr("Transférer un membre d'équipage", "transfer_crew", "/station/{station_id}/crew/transfer/{crew_id}/{from_ship}/{to_ship}", "transfer_crew", [
+  Déplace le membre d'équipage `crew_id` du vaisseau `from_ship` vers le vaisseau `to_ship`,
+  tous deux à quai sur la station.
+
+  Un pilote devient le pilote du vaisseau, un opérateur est assigné au premier module
+  sans opérateur, dont l'ID est retourné (clé `module`)
+], "un des vaisseaux n'est pas sur la station ou est occupé, le membre d'équipage n'est pas à bord, ou aucun poste n'est libre sur le vaisseau de destination")
+
+#descr("Licencier un membre d'équipage", "fire_crew", "/station/{station_id}/crew/fire/{crew_id}", "fire_crew", [
+  Licencie un membre d'équipage inactif de la station, il ne sera plus payé.
+
+  Une indemnité égale à 10 minutes de son salaire lui est versée (clé `severance`)
+], "le membre d'équipage n'est pas inactif ou vous n'avez pas assez d'argent")
+
 #descr("Lister les améliorations de l'équipage", "list_crew_upgr",
   "/station/{station_id}/crew/upgrade/ship/{ship_id}", "get_crew_upgrades", [
     L'appel à cet endpoint retourne, pour chaque membre d'équipage du vaisseau, le rang
@@ -418,14 +710,17 @@ d'erreurs où l'ID attendu n'est pas le bon
     - `Pilot`: Réduis la consommation de carburant, augmente la vitesse
     - `Opérator`: Augmente la quantité de resources extraites, débloque certaines resources
     - `Trader`: Réduis les frais de trading (voir @upgrade_trader)
-  ], "le vaisseau n'est pas dans une station ou vous n'avez pas assez d'argent"
+
+    Chaque gain de rang apporte moins que le précédent. Le rang maximal (clé `max-rank`)
+    est de 20, et de 10 pour les soldats
+  ], "le vaisseau n'est pas dans une station, vous n'avez pas assez d'argent ou le membre a atteint son rang maximal"
 )
 
 #descr("Augmenter le rang du trader d'une station", "upgrade_trader",
   "/station/{station_id}/crew/upgrade/trader", "upgrade_trader", [
     Améliore le rang du `trader` assigné à une station. \
     Voir @upgrade_crew pour comprendre les effets
-  ], "aucun trader n'est assigné à cette station ou vous n'avez pas assez d'argent"
+  ], "aucun trader n'est assigné à cette station, vous n'avez pas assez d'argent ou le trader a atteint son rang maximal"
 )
 
 #pagebreak()
@@ -484,6 +779,16 @@ Chaque transaction retourne une donnée `MarketTx` comprenant:
   ], none
 )
 
+#descr("Obtenir l'inflation", "inflation",
+  "/market/inflation", "get_inflation", [
+    Le prix de tout l'équipement (vaisseaux, modules, améliorations, équipage, cargo
+    des stations) ainsi que les salaires augmentent avec le temps.
+
+    Retourne l'indice d'inflation actuel (`inflation`), par lequel sont multipliés
+    tous ces prix, et la courbe suivie par l'inflation dans cette partie (`curve`).
+  ], none
+)
+
 #descr("Obtenir le pourcentage de frais", "fee_rate",
   "/market/{station_id}/fee_rate", "get_fee_rate", [
     En fonction du rang du trader assigné à cette station, retourne le montant de frais
@@ -517,20 +822,42 @@ Chaque transaction retourne une donnée `MarketTx` comprenant:
   En cas de succès, retournera `{"ping": "pong"}`
 ], none)
 
+Chaque réponse du serveur contient un champ `tick`, le nombre de ticks de simulation
+écoulés depuis le démarrage du jeu.
+
+#descr("Avancer le jeu manuellement", "tick", "/tick/{n}", "tick_server_n", [
+  Lorsque l'horloge du jeu est en mode manuel, le jeu n'avance que lorsque cette
+  requête est appelée, de `n` ticks (ou d'un seul tick avec `/tick`).
+
+  Nécessite le jeton d'administration. Une requête ne peut pas avancer le jeu de plus
+  de 1000 ticks.
+], "le jeton d'administration est invalide, l'horloge du jeu n'est pas en mode manuel, le jeu est en pause, ou `n` dépasse 1000")
+
 #descr("Récupérer les logs du système", "syslog", "/syslogs", "get_syslogs", [
   Lorsque le jeu réalise une action automatiquement, ou si une alerte est lancée,
   cela sera visible dans les logs associés au joueur.
 
   Ainsi, les logs montreront lorsque:
-  - Le jeu a commencé pour ce joueur
-  - Le joueur a perdu
-  - Un vaisseau a atteint sa destination
-  - Un vaisseau a arrêté d'extraire des resources car son cargo est plein
-  - Un vaisseau a été détruit
+  - Le jeu a commencé pour ce joueur (`GameStarted`)
+  - Le joueur a perdu (`GameLost`)
+  - Un vaisseau a atteint sa destination (`ShipArrived`), avec sa position et le type de
+    lieu atteint (clé `location`: `Station`, `Planet` ou `EmptySpace`)
+  - Un vaisseau s'est immobilisé en plein vol, son réservoir étant vide (`ShipOutOfFuel`)
+  - Un vaisseau a été remorqué jusqu'à une station (`ShipTowed`, voir @tow)
+  - Un vaisseau a arrêté d'extraire des resources car son cargo est plein (`CargoFull`), avec
+    les quantités extraites depuis le début de l'extraction (clé `extracted`)
+  - Un vaisseau a terminé de réparer sa coque (`RepairStopped`, voir @ship_repair)
+  - Un vaisseau a été détruit (`ShipDestroyed`)
+  - Un membre d'équipage a été embauché (`CrewHired`) ou assigné à un poste (`CrewAssigned`)
+  - Une transaction a été effectuée sur le marché (`MarketTrade`), avec son détail (clé `tx`)
+  - Un succès a été débloqué (`AchievementUnlocked`)
 
   Et produiront des alertes lorsque:
-  - Le déchargement des resources d'un vaisseau n'est pas possible (voir @unload)
-  - Il ne reste que 60 secondes avant que les frais n'épuisent les réserves d'argent
+  - Le déchargement des resources d'un vaisseau n'est pas possible (`UnloadedNothing`, voir @unload)
+  - Il ne reste que 60 secondes avant que les frais n'épuisent les réserves d'argent (`LowFunds`)
+  - L'usure de la coque d'un vaisseau en vol dépasse 50%, 80% puis 95% (`HullCritical`)
+  - Le prix d'une resource a varié de plus de 10% d'un coup (`PriceSpike`), avec l'ancien
+    et le nouveau prix
 ], none)
 
 // TO DO IMPORTANT  Add the /resources endpoint
@@ -122,8 +122,8 @@ while True:
             p["score"] = -1.0
 
     buffer += "{} Players still in the game\n".format(len([True for p in info.values() if not p["lost"]]))
-    players = sorted(info.items(), key=lambda p: p[1]["score"] + p[1]["potential"], reverse=True)[:NMAX]
-    max_score = max([max(v["score"], 0) + v["potential"] for v in info.values()])
+    players = sorted(info.items(), key=lambda p: p[1]["score"], reverse=True)[:NMAX]
+    max_score = max([max(v["score"], 0) for v in info.values()])
     maxn = max([len(data["name"]) for (_, data) in players])
     for (player, data) in players:
         if player not in HIST:
@@ -134,7 +134,7 @@ while True:
             buffer += "Player {} LOST".format(data["name"] + " " * spaces) + "\n"
             continue
 
-        s = max(0, data["score"]) + data["potential"]
+        s = max(0, data["score"])
         if data["age"] == 0:
             avg = 0.0
         else:
@@ -142,7 +142,7 @@ while True:
         HIST[player].append((s, avg))
         avg_lasts = max([n[1] for n in HIST[player][-30:]])
 
-        bar = mkbar(data["score"], data["potential"], max_score)
+        bar = mkbar(s - data["potential"], data["potential"], max_score)
         buffer += "Player {} {} {} (~{}/sec)\tpotential: {}".format(
             data["name"] + " " * spaces, bar, round(data["score"], 2),
             round(avg_lasts, 2),
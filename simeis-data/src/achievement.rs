@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+pub const MILLIONAIRE_MONEY: f64 = 1_000_000.0;
+pub const FLEET_ADMIRAL_SHIPS: usize = 5;
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, EnumIter,
+)]
+pub enum Achievement {
+    FirstShip,
+    FirstExtraction,
+    FirstSale,
+    FirstCrewUpgrade,
+    FirstGold,
+    FirstDiscovery,
+    FleetAdmiral,
+    Millionaire,
+}
+
+impl Achievement {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::FirstShip => "Buy your first ship",
+            Achievement::FirstExtraction => "Start extracting resources from a planet",
+            Achievement::FirstSale => "Sell resources on the market",
+            Achievement::FirstCrewUpgrade => "Upgrade the rank of a crew member",
+            Achievement::FirstGold => "Extract gold",
+            Achievement::FirstDiscovery => "Be the first to discover a sector of the galaxy",
+            Achievement::FleetAdmiral => "Own 5 ships at the same time",
+            Achievement::Millionaire => "Have one million credits",
+        }
+    }
+
+    // Money given when unlocked, scaled by the configuration of the game
+    pub fn reward(&self) -> f64 {
+        match self {
+            Achievement::FirstShip => 2000.0,
+            Achievement::FirstExtraction => 1000.0,
+            Achievement::FirstSale => 1000.0,
+            Achievement::FirstCrewUpgrade => 1500.0,
+            Achievement::FirstGold => 5000.0,
+            Achievement::FirstDiscovery => 3000.0,
+            Achievement::FleetAdmiral => 20000.0,
+            Achievement::Millionaire => 50000.0,
+        }
+    }
+
+    pub fn list(reward_scale: f64) -> Vec<serde_json::Value> {
+        Achievement::iter()
+            .map(|a| {
+                serde_json::json!({
+                    "name": a,
+                    "description": a.description(),
+                    "reward": a.reward() * reward_scale,
+                })
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_unlock_once() {
+    let mut player = crate::player::Player::new(0, (0, (0, 0, 0)), "achiever".to_string());
+    player.unlock(Achievement::FirstSale);
+    player.unlock(Achievement::FirstGold);
+    player.unlock(Achievement::FirstSale);
+    assert_eq!(
+        player.take_unlocked(),
+        vec![Achievement::FirstSale, Achievement::FirstGold]
+    );
+    player.unlock(Achievement::FirstGold);
+    assert!(player.take_unlocked().is_empty());
+    assert_eq!(player.achievements.len(), 2);
+}
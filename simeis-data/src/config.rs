@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::market::InflationCurve;
+
+const DEFAULT_TICK_PERIOD: Duration = Duration::from_millis(20);
+const DEFAULT_EXPORT_PATH: &str = "simeis-export.json";
+const DEFAULT_INFLATION: InflationCurve = InflationCurve::Linear(0.05);
+
+// Configuration of a game, set when the server starts
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    // Real time between two ticks of the game
+    pub tick_period: Duration,
+    // Game seconds elapsing for each real second
+    pub time_scale: f64,
+    // If true, the game only advances when asked to
+    pub manual_ticks: bool,
+    // Token required to use the admin API, disabled if not set
+    pub admin_token: Option<String>,
+    // Where the snapshots of the game are written
+    pub export_path: PathBuf,
+    // Multiplies the money given by achievements, 0 disables the rewards
+    pub achievement_rewards: f64,
+    pub inflation: InflationCurve,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            tick_period: DEFAULT_TICK_PERIOD,
+            time_scale: 1.0,
+            manual_ticks: cfg!(feature = "testing"),
+            admin_token: None,
+            export_path: PathBuf::from(DEFAULT_EXPORT_PATH),
+            achievement_rewards: 1.0,
+            inflation: DEFAULT_INFLATION,
+        }
+    }
+}
+
+impl GameConfig {
+    // Overrides the default configuration with the SIMEIS_* environment variables
+    pub fn from_env() -> GameConfig {
+        let mut config = GameConfig::default();
+        if let Some(ms) = get_env::<u64>("SIMEIS_TICK_PERIOD_MS") {
+            config.tick_period = Duration::from_millis(ms.max(1));
+        }
+        if let Some(scale) = get_env::<f64>("SIMEIS_TIME_SCALE") {
+            if scale > 0.0 {
+                config.time_scale = scale;
+            }
+        }
+        if let Some(manual) = get_env::<bool>("SIMEIS_MANUAL_TICKS") {
+            config.manual_ticks = manual;
+        }
+        config.admin_token = std::env::var("SIMEIS_ADMIN_TOKEN")
+            .ok()
+            .filter(|t| !t.is_empty());
+        if let Some(scale) = get_env::<f64>("SIMEIS_ACHIEVEMENT_REWARDS") {
+            if scale >= 0.0 {
+                config.achievement_rewards = scale;
+            }
+        }
+        if let Some(curve) = get_env::<InflationCurve>("SIMEIS_INFLATION") {
+            config.inflation = curve;
+        }
+        if let Ok(path) = std::env::var("SIMEIS_EXPORT_PATH") {
+            config.export_path = PathBuf::from(path);
+        }
+        config
+    }
+
+    // Always false if no admin token was configured
+    pub fn is_admin_token(&self, token: &str) -> bool {
+        self.admin_token.as_ref().is_some_and(|t| t == token)
+    }
+}
+
+fn get_env<T: FromStr>(name: &str) -> Option<T> {
+    let val = std::env::var(name).ok()?;
+    let Ok(parsed) = T::from_str(val.trim()) else {
+        log::warn!("Invalid value for {name}: {val:?}, ignored");
+        return None;
+    };
+    Some(parsed)
+}
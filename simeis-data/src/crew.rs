@@ -1,9 +1,25 @@
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use strum::{EnumString, IntoStaticStr};
+use strum::{EnumIter, EnumString, IntoStaticStr};
+
+use crate::errors::Errcode;
+
+pub mod recruitment;
 
 const WAGE_INC_RANK_POWF: f64 = 0.85;
 const RANK_PRICE_WAGE_MULT: f64 = 1900.0;
+// A fired crew member gets 10 minutes of wages
+const SEVERANCE_WAGE_SEC: f64 = 600.0;
+
+// The experience improves the performances up to 25%, with diminishing returns
+const XP_BONUS_MAX: f64 = 0.25;
+// A crew member with no morale left only performs at 70%
+const MORALE_MIN_PERF: f64 = 0.7;
+// Without enough money to pay the wages, the morale is lost in 10 minutes
+const MORALE_UNPAID_LOSS: f64 = 1.0 / 600.0;
+// When paid, the morale is fully recovered in 20 minutes
+const MORALE_RECOVERY: f64 = 1.0 / 1200.0;
+pub const MORALE_DANGER_LOSS: f64 = 0.1;
 
 pub type CrewId = u32;
 
@@ -13,12 +29,35 @@ impl Crew {
     pub fn sum_wages(&self) -> f64 {
         self.0.values().map(|crew| crew.wage()).sum::<f64>()
     }
+
+    pub fn update_morale(&mut self, tdelta: f64, underpaid: bool) {
+        for cm in self.0.values_mut() {
+            cm.update_morale(tdelta, underpaid);
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CrewMember {
     pub member_type: CrewMemberType,
     pub rank: u8,
+    #[serde(default)]
+    pub xp: f64,
+    #[serde(default = "full_morale")]
+    pub morale: f64,
+    #[serde(default)]
+    pub traits: Vec<CrewTrait>,
+    // Multiplier on the wage asked when hired
+    #[serde(default = "neutral_rate")]
+    pub wage_rate: f64,
+}
+
+fn full_morale() -> f64 {
+    1.0
+}
+
+fn neutral_rate() -> f64 {
+    1.0
 }
 
 impl From<CrewMemberType> for CrewMember {
@@ -26,6 +65,10 @@ impl From<CrewMemberType> for CrewMember {
         CrewMember {
             member_type,
             rank: 1,
+            xp: 0.0,
+            morale: full_morale(),
+            traits: vec![],
+            wage_rate: neutral_rate(),
         }
     }
 }
@@ -38,17 +81,170 @@ impl CrewMember {
             CrewMemberType::Trader => 2.6,
             CrewMemberType::Soldier => 1.5,
         };
-        base * (self.rank as f64).powf(WAGE_INC_RANK_POWF)
+        base * (self.rank as f64).powf(WAGE_INC_RANK_POWF) * self.wage_rate
+    }
+
+    #[inline]
+    pub fn price_next_rank(&self, inflation: f64) -> f64 {
+        self.wage() * RANK_PRICE_WAGE_MULT * inflation
+    }
+
+    pub fn rank_up(&mut self) -> Result<u8, Errcode> {
+        let max = self.member_type.max_rank();
+        if self.rank >= max {
+            return Err(Errcode::MaxRankReached(max));
+        }
+        self.rank += 1;
+        Ok(self.rank)
+    }
+
+    // Experience needed to get 63% of the maximal bonus
+    fn xp_scale(&self) -> f64 {
+        match self.member_type {
+            CrewMemberType::Pilot => 20000.0,   // Distance flown
+            CrewMemberType::Operator => 5000.0, // Volume extracted
+            CrewMemberType::Trader => 50000.0,  // Units traded
+            CrewMemberType::Soldier => 1.0,
+        }
+    }
+
+    pub fn gain_xp(&mut self, amnt: f64) {
+        self.xp += amnt.max(0.0);
+    }
+
+    // Multiplier on the performances, 1.0 for a new crew member
+    pub fn performance(&self) -> f64 {
+        let xp_bonus = XP_BONUS_MAX * (1.0 - (-self.xp / self.xp_scale()).exp());
+        let morale = MORALE_MIN_PERF + ((1.0 - MORALE_MIN_PERF) * self.morale);
+        (1.0 + xp_bonus) * morale
+    }
+
+    // Product of the effects of the traits of this crew member
+    pub fn trait_factor<F: Fn(&CrewTrait) -> f64>(&self, effect: F) -> f64 {
+        self.traits.iter().map(effect).product()
+    }
+
+    pub fn lose_morale(&mut self, amnt: f64) {
+        let amnt = if amnt > 0.0 {
+            amnt * self.trait_factor(CrewTrait::morale_loss_factor)
+        } else {
+            amnt
+        };
+        self.morale = (self.morale - amnt).clamp(0.0, 1.0);
+    }
+
+    // The crew worries when the player is about to be unable to pay the wages
+    pub fn update_morale(&mut self, tdelta: f64, underpaid: bool) {
+        if underpaid {
+            self.lose_morale(MORALE_UNPAID_LOSS * tdelta);
+        } else {
+            self.lose_morale(-MORALE_RECOVERY * tdelta);
+        }
     }
 
     #[inline]
-    pub fn price_next_rank(&self) -> f64 {
-        self.wage() * RANK_PRICE_WAGE_MULT
+    pub fn severance(&self, inflation: f64) -> f64 {
+        self.wage() * SEVERANCE_WAGE_SEC * inflation
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum CrewTrait {
+    // Pilots
+    Frugal,
+    Reckless,
+    // Operators
+    Meticulous,
+    Clumsy,
+    // Traders
+    Haggler,
+    Careless,
+    // Anyone
+    Loyal,
+    Greedy,
+}
+
+impl CrewTrait {
+    pub fn applies_to(&self, ctype: &CrewMemberType) -> bool {
+        match self {
+            CrewTrait::Frugal | CrewTrait::Reckless => ctype == &CrewMemberType::Pilot,
+            CrewTrait::Meticulous | CrewTrait::Clumsy => ctype == &CrewMemberType::Operator,
+            CrewTrait::Haggler | CrewTrait::Careless => ctype == &CrewMemberType::Trader,
+            CrewTrait::Loyal | CrewTrait::Greedy => true,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            CrewTrait::Frugal => "-10% fuel consumption",
+            CrewTrait::Reckless => "+10% speed, +15% fuel consumption",
+            CrewTrait::Meticulous => "+10% extraction rate",
+            CrewTrait::Clumsy => "-15% extraction rate",
+            CrewTrait::Haggler => "-10% trading fees",
+            CrewTrait::Careless => "+15% trading fees",
+            CrewTrait::Loyal => "Loses morale twice slower",
+            CrewTrait::Greedy => "+20% wage",
+        }
+    }
+
+    // Is this trait an advantage
+    pub fn is_good(&self) -> bool {
+        matches!(
+            self,
+            CrewTrait::Frugal | CrewTrait::Meticulous | CrewTrait::Haggler | CrewTrait::Loyal
+        )
+    }
+
+    pub fn fuel_factor(&self) -> f64 {
+        match self {
+            CrewTrait::Frugal => 0.9,
+            CrewTrait::Reckless => 1.15,
+            _ => 1.0,
+        }
+    }
+
+    pub fn speed_factor(&self) -> f64 {
+        match self {
+            CrewTrait::Reckless => 1.1,
+            _ => 1.0,
+        }
+    }
+
+    pub fn extraction_factor(&self) -> f64 {
+        match self {
+            CrewTrait::Meticulous => 1.1,
+            CrewTrait::Clumsy => 0.85,
+            _ => 1.0,
+        }
+    }
+
+    pub fn fee_factor(&self) -> f64 {
+        match self {
+            CrewTrait::Haggler => 0.9,
+            CrewTrait::Careless => 1.15,
+            _ => 1.0,
+        }
+    }
+
+    pub fn wage_factor(&self) -> f64 {
+        match self {
+            CrewTrait::Greedy => 1.2,
+            _ => 1.0,
+        }
+    }
+
+    pub fn morale_loss_factor(&self) -> f64 {
+        match self {
+            CrewTrait::Loyal => 0.5,
+            _ => 1.0,
+        }
     }
 }
 
 #[allow(dead_code)]
-#[derive(EnumString, IntoStaticStr, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(
+    EnumString, EnumIter, IntoStaticStr, Debug, Serialize, Deserialize, Clone, PartialEq, Eq,
+)]
 #[strum(ascii_case_insensitive)]
 pub enum CrewMemberType {
     Pilot,
@@ -56,3 +252,26 @@ pub enum CrewMemberType {
     Trader,
     Soldier,
 }
+
+impl CrewMemberType {
+    pub fn max_rank(&self) -> u8 {
+        match self {
+            CrewMemberType::Pilot | CrewMemberType::Operator | CrewMemberType::Trader => 20,
+            CrewMemberType::Soldier => 10,
+        }
+    }
+}
+
+#[test]
+fn test_crew_performance() {
+    let mut cm = CrewMember::from(CrewMemberType::Pilot);
+    assert_eq!(cm.performance(), 1.0);
+    cm.gain_xp(1e9);
+    assert!((cm.performance() - (1.0 + XP_BONUS_MAX)).abs() < 1e-9);
+
+    cm.update_morale(1e9, true);
+    assert_eq!(cm.morale, 0.0);
+    assert!((cm.performance() - (1.0 + XP_BONUS_MAX) * MORALE_MIN_PERF).abs() < 1e-9);
+    cm.update_morale(1e9, false);
+    assert_eq!(cm.morale, 1.0);
+}
@@ -0,0 +1,109 @@
+use rand::Rng;
+use serde::Serialize;
+
+use super::{CrewId, CrewMember, CrewMemberType, CrewTrait};
+
+// Number of candidates waiting to be hired in a station
+pub const CANDIDATES_POOL_SIZE: usize = 5;
+// Game seconds between two renewals of the candidates
+pub const CANDIDATES_REFRESH_SEC: f64 = 300.0;
+
+// Hiring a crew member costs 5 minutes of its wage
+const HIRING_COST_WAGE_SEC: f64 = 300.0;
+// The starting rank of the candidates goes up to this, most of them are rank 1
+const CANDIDATE_RANK_MAX: f64 = 4.0;
+const TRAIT_PROBA: f64 = 0.35;
+const WAGE_RATE_SPREAD: f64 = 0.15;
+// Each good trait makes the candidate ask for a higher wage
+const GOOD_TRAIT_WAGE: f64 = 0.05;
+
+// Pairs of opposite traits, a candidate can only have one of them
+const TRAIT_PAIRS: [(CrewTrait, CrewTrait); 4] = [
+    (CrewTrait::Frugal, CrewTrait::Reckless),
+    (CrewTrait::Meticulous, CrewTrait::Clumsy),
+    (CrewTrait::Haggler, CrewTrait::Careless),
+    (CrewTrait::Loyal, CrewTrait::Greedy),
+];
+
+#[inline]
+pub fn hiring_cost(member: &CrewMember, inflation: f64) -> f64 {
+    member.wage() * HIRING_COST_WAGE_SEC * inflation
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Candidate {
+    pub id: CrewId,
+    pub member: CrewMember,
+}
+
+impl Candidate {
+    pub fn random<R: Rng>(rng: &mut R) -> Candidate {
+        let member_type = match rng.random_range(0..3) {
+            0 => CrewMemberType::Pilot,
+            1 => CrewMemberType::Operator,
+            _ => CrewMemberType::Trader,
+        };
+        let rank = 1 + (rng.random::<f64>().powi(2) * CANDIDATE_RANK_MAX) as u8;
+
+        let mut traits = vec![];
+        for (good, bad) in TRAIT_PAIRS {
+            if !good.applies_to(&member_type) || !rng.random_bool(TRAIT_PROBA) {
+                continue;
+            }
+            traits.push(if rng.random_bool(0.5) { good } else { bad });
+        }
+
+        let nb_good = traits.iter().filter(|t| t.is_good()).count() as f64;
+        let mut member = CrewMember {
+            rank,
+            traits,
+            ..CrewMember::from(member_type)
+        };
+        member.wage_rate = rng.random_range((1.0 - WAGE_RATE_SPREAD)..(1.0 + WAGE_RATE_SPREAD))
+            * (1.0 + nb_good * GOOD_TRAIT_WAGE)
+            * member.trait_factor(CrewTrait::wage_factor);
+        Candidate {
+            id: rng.random(),
+            member,
+        }
+    }
+
+    pub fn to_json(&self, inflation: f64) -> serde_json::Value {
+        let traits: Vec<serde_json::Value> = self
+            .member
+            .traits
+            .iter()
+            .map(|t| serde_json::json!({ "name": t, "description": t.description() }))
+            .collect();
+        serde_json::json!({
+            "id": self.id,
+            "member_type": self.member.member_type,
+            "rank": self.member.rank,
+            "traits": traits,
+            "wage": self.member.wage() * inflation,
+            "hiring_cost": hiring_cost(&self.member, inflation),
+        })
+    }
+}
+
+pub fn candidates_pool<R: Rng>(rng: &mut R) -> Vec<Candidate> {
+    (0..CANDIDATES_POOL_SIZE)
+        .map(|_| Candidate::random(rng))
+        .collect()
+}
+
+#[test]
+fn test_candidates() {
+    crate::tests::create_property_based_test(1000, &[], |rng| {
+        let candidate = Candidate::random(rng);
+        let member = &candidate.member;
+        assert!(member.rank >= 1 && member.rank <= 1 + CANDIDATE_RANK_MAX as u8);
+        assert!(member.traits.len() <= TRAIT_PAIRS.len());
+        assert!(member
+            .traits
+            .iter()
+            .all(|t| t.applies_to(&member.member_type)));
+        assert!(member.wage_rate > 0.0);
+        assert!(hiring_cost(member, 1.0) > 0.0);
+    });
+}
@@ -21,6 +21,7 @@ pub enum Errcode {
     CargoFull,
     NoTraderAssigned,
     NoPilotAssigned,
+    NoScanner,
     BuyNothing,
     SellNothing,
     NoFuelInCargo,
@@ -28,6 +29,18 @@ pub enum Errcode {
     CrewMemberNotFound(crate::crew::CrewId),
     PlayerLost,
     GameSignalSend,
+    TooManyPlayers,
+    NotAdmin,
+    ClockNotManual,
+    GamePaused,
+    ShipCargoNotEmpty,
+    MaxRankReached(u8),
+    NoModuleSlot(u8),
+    ShipsNotTogether,
+    NoOperatorOnBoard,
+    ShipNotRepairing,
+    ShipNotStranded,
+    NoModuleIdLeft,
 }
 
 impl Errcode {
@@ -68,7 +81,22 @@ impl Errcode {
             Errcode::CrewMemberNotFound(id) => format!("Crew member of id {id} not found"),
             Errcode::PlayerLost => "This player lost the game and cannot play anymore".to_string(),
             Errcode::NoPilotAssigned => "No pilot is assigned on this ship".to_string(),
+            Errcode::NoScanner => "This ship doesn't have any scanner module with an operator".to_string(),
             Errcode::GameSignalSend => "Error while sending a game signal to state".to_string(),
+            Errcode::TooManyPlayers => "No more players can join this game".to_string(),
+            Errcode::NotAdmin => "A valid admin token is required for this request".to_string(),
+            Errcode::ClockNotManual => "The game clock is not in manual mode".to_string(),
+            Errcode::GamePaused => "The game is paused".to_string(),
+            Errcode::ShipCargoNotEmpty => "The cargo of the ship must be empty".to_string(),
+            Errcode::NoModuleSlot(free) => format!("Not enough module slots, {free} left"),
+            Errcode::ShipsNotTogether => "The ships are not on the same position".to_string(),
+            Errcode::NoOperatorOnBoard => "An operator is needed aboard the ship".to_string(),
+            Errcode::ShipNotRepairing => "The ship is not repairing its hull".to_string(),
+            Errcode::ShipNotStranded => {
+                "The ship can fly back to the station by itself".to_string()
+            }
+            Errcode::NoModuleIdLeft => "No module ID is left on this ship".to_string(),
+            Errcode::MaxRankReached(rank) => format!("The maximal rank {rank} is already reached"),
         }
     }
 }
@@ -1,15 +1,17 @@
 #![allow(unexpected_cfgs)]
+use crate::player::PlayerId;
+use crate::ship::ShipInfo;
 use rand::rngs::ThreadRng;
 use rand::Rng;
 use scan::ScanResult;
 use station::StationId;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub type SpaceUnit = u32;
 pub type SpaceCoord = (SpaceUnit, SpaceUnit, SpaceUnit);
-type GalaxySector = (
+pub type GalaxySector = (
     (SpaceUnit, SpaceUnit),
     (SpaceUnit, SpaceUnit),
     (SpaceUnit, SpaceUnit),
@@ -19,6 +21,8 @@ const SECTOR_SIZE: (SpaceUnit, SpaceUnit, SpaceUnit) = (5000, 5000, 5000);
 const PLANETS_PER_SECTOR: usize = 3;
 const STATION_FPLANET_DIST: f64 = 500.0;
 
+pub mod economy;
+pub mod map;
 pub mod planet;
 pub mod scan;
 pub mod station;
@@ -30,24 +34,24 @@ pub enum SpaceObject {
     Planet(Arc<planet::Planet>),
 }
 
+// Objects are indexed by the sector they are in,
+// a sector is present in the index once it has been discovered
 pub struct Galaxy {
-    objects: BTreeMap<SpaceCoord, SpaceObject>,
-    discovered: Vec<GalaxySector>, // TO DO BTreeMap instead ?
+    sectors: HashMap<GalaxySector, BTreeMap<SpaceCoord, SpaceObject>>,
 }
 
 impl Galaxy {
     pub fn init() -> Galaxy {
         Galaxy {
-            objects: BTreeMap::new(),
-            discovered: vec![],
+            sectors: HashMap::new(),
         }
     }
 
     // X, Y and Z can be any point from the given sector
-    // Returns the index in the "discovered" vector
-    pub fn generate_sector(&mut self, coord: &SpaceCoord) -> usize {
+    pub fn generate_sector(&mut self, coord: &SpaceCoord) -> GalaxySector {
         let (x, y, z) = coord;
-        let (secx, secy, secz) = compute_sector(*x, *y, *z);
+        let sector = compute_sector(*x, *y, *z);
+        let (secx, secy, secz) = sector;
         log::debug!(
             "Generating sector ({}-{}, {}-{}, {}-{})",
             secx.0,
@@ -57,8 +61,7 @@ impl Galaxy {
             secz.0,
             secz.1,
         );
-        let ind = self.discovered.len();
-        self.discovered.push((secx, secy, secz));
+        self.sectors.entry(sector).or_default();
         let mut rng = rand::rng();
         for _ in 0..PLANETS_PER_SECTOR {
             let x = rng.random_range(secx.0..secx.1);
@@ -72,48 +75,51 @@ impl Galaxy {
                 continue;
             }
         }
-        ind
+        sector
     }
 
+    #[inline]
     pub fn is_discovered(&self, coord: &SpaceCoord) -> bool {
-        let (x, y, z) = coord;
-        for ((sx, ex), (sy, ey), (sz, ez)) in self.discovered.iter() {
-            if (x < sx) || (x > ex) || (y < sy) || (y > ey) || (z < sz) || (z > ez) {
-                continue;
+        self.sectors
+            .contains_key(&compute_sector(coord.0, coord.1, coord.2))
+    }
+
+    // Generates all the sectors in range of a scanner of this rank
+    // Returns how many sectors were discovered for the first time
+    pub fn discover_around(&mut self, rank: u8, center: &SpaceCoord) -> usize {
+        let mut ndiscovered = 0;
+        for sector in sectors_around(center, scan::scan_radius(rank)) {
+            if !self.sectors.contains_key(&sector) {
+                self.generate_sector(&(sector.0 .0, sector.1 .0, sector.2 .0));
+                ndiscovered += 1;
             }
-            return true;
         }
-        false
+        ndiscovered
     }
 
     pub fn get<'a>(&'a self, coord: &SpaceCoord) -> Option<&'a SpaceObject> {
-        self.objects.get(coord)
+        self.sectors
+            .get(&compute_sector(coord.0, coord.1, coord.2))?
+            .get(coord)
     }
 
+    // Objects can only be inserted in a sector that has been generated
     pub fn insert(&mut self, coord: &SpaceCoord, obj: SpaceObject) -> Option<()> {
-        if self.objects.contains_key(coord) {
+        let sector = self
+            .sectors
+            .get_mut(&compute_sector(coord.0, coord.1, coord.2))?;
+        if sector.contains_key(coord) {
             return None;
         }
-        self.objects.insert(*coord, obj);
+        sector.insert(*coord, obj);
         Some(())
     }
 
-    fn list_objects_in_sector(&self, sector: &GalaxySector) -> Vec<&SpaceObject> {
-        let mut objects = vec![];
-        for (coord, obj) in self.objects.iter() {
-            let (x, y, z) = coord;
-            if (x < &sector.0 .0) || (x > &sector.0 .1) {
-                continue;
-            }
-            if (y < &sector.1 .0) || (y > &sector.1 .1) {
-                continue;
-            }
-            if (z < &sector.2 .0) || (z > &sector.2 .1) {
-                continue;
-            }
-            objects.push(obj);
-        }
-        objects
+    fn list_objects_in_sector(&self, sector: &GalaxySector) -> Vec<(&SpaceCoord, &SpaceObject)> {
+        let Some(objects) = self.sectors.get(sector) else {
+            return vec![];
+        };
+        objects.iter().collect()
     }
 
     pub async fn get_station(&self, coord: &SpaceCoord) -> Option<Arc<RwLock<station::Station>>> {
@@ -124,6 +130,17 @@ impl Galaxy {
         Some(station.clone())
     }
 
+    pub fn all_stations(&self) -> Vec<Arc<RwLock<station::Station>>> {
+        self.sectors
+            .values()
+            .flat_map(|objects| objects.values())
+            .filter_map(|obj| match obj {
+                SpaceObject::BaseStation(station) => Some(station.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub async fn get_planet(&self, coord: &SpaceCoord) -> Option<Arc<planet::Planet>> {
         let obj = self.get(coord)?;
         let SpaceObject::Planet(planet) = obj else {
@@ -133,7 +150,7 @@ impl Galaxy {
     }
 
     // TO DO (#11) Generate based on the galaxy
-    pub async fn init_new_station(&mut self) -> (StationId, SpaceCoord) {
+    pub async fn init_new_station(&mut self, owner: PlayerId) -> (StationId, SpaceCoord) {
         let mut rng = rand::rng();
 
         let mut seccoord = (rng.random(), rng.random(), rng.random());
@@ -141,14 +158,12 @@ impl Galaxy {
             seccoord = (rng.random(), rng.random(), rng.random());
         }
         let id = rng.random();
-        let ind = self.generate_sector(&seccoord);
-        let sector = self.discovered.get(ind).unwrap();
+        let sector = &self.generate_sector(&seccoord);
 
-        let Some(SpaceObject::Planet(pla)) = self
+        let Some((_, SpaceObject::Planet(pla))) = self
             .list_objects_in_sector(sector)
-            .iter()
-            .filter(|obj| matches!(obj, SpaceObject::Planet(_)))
-            .nth(0)
+            .into_iter()
+            .find(|(_, obj)| matches!(obj, SpaceObject::Planet(_)))
         else {
             unreachable!("Planet inside generated sector");
         };
@@ -164,8 +179,8 @@ impl Galaxy {
             let mut mindist = None;
             for pla in self
                 .list_objects_in_sector(sector)
-                .iter()
-                .filter_map(|obj| {
+                .into_iter()
+                .filter_map(|(_, obj)| {
                     if let SpaceObject::Planet(p) = obj {
                         Some(p)
                     } else {
@@ -193,22 +208,32 @@ impl Galaxy {
                 panic!("Too many retries");
             }
         }
-        let station = Arc::new(RwLock::new(station::Station::init(id, coord)));
+        let station = Arc::new(RwLock::new(station::Station::init(id, owner, coord)));
         self.insert(&coord, SpaceObject::BaseStation(station))
             .unwrap();
         (id, coord)
     }
 
-    pub async fn scan_sector(&self, rank: u8, center: &SpaceCoord) -> ScanResult {
-        let strengh = (rank - 1) as f64;
+    pub async fn scan_sector(
+        &self,
+        rank: u8,
+        center: &SpaceCoord,
+        ships: &[ShipInfo],
+    ) -> ScanResult {
+        let radius = scan::scan_radius(rank);
         let mut results = ScanResult::empty();
-        debug_assert!(strengh >= 0.0);
-        for sector in sectors_around(center, strengh) {
-            for obj in self.list_objects_in_sector(&sector) {
-                results.add(rank, obj).await;
+        for sector in sectors_around(center, radius) {
+            let Some(objects) = self.sectors.get(&sector) else {
+                continue;
+            };
+            for (coord, obj) in objects.iter() {
+                if get_distance(center, coord) <= radius {
+                    results.add(rank, obj).await;
+                }
             }
+            results.sectors.push(sector);
         }
-        debug_assert!(!results.planets.is_empty()); // We should always have some planets
+        results.add_ships(rank, center, radius, ships);
         results
     }
 }
@@ -236,7 +261,7 @@ pub fn get_direction(a: &SpaceCoord, b: &SpaceCoord) -> (f64, f64, f64) {
 }
 
 // TO DO (#33)   Unit tests on this one
-fn compute_sector(x: SpaceUnit, y: SpaceUnit, z: SpaceUnit) -> GalaxySector {
+pub fn compute_sector(x: SpaceUnit, y: SpaceUnit, z: SpaceUnit) -> GalaxySector {
     let start_x = x - (x % SECTOR_SIZE.0);
     let end_x = start_x.saturating_add(SECTOR_SIZE.0);
     let start_y = y - (y % SECTOR_SIZE.1);
@@ -263,50 +288,47 @@ fn is_in_sector(coord: &SpaceCoord, sector: &GalaxySector) -> bool {
         && coord.2 < sector.2 .1
 }
 
-// TO DO (#27)    Make this scan use a sphere from the center point
+// All the sectors intersecting the sphere of the given radius around the center point
 fn sectors_around(center: &SpaceCoord, radius: f64) -> Vec<GalaxySector> {
     let mut sectors = vec![];
-    let centersec = compute_sector(center.0, center.1, center.2);
-
-    let xsecstart = ((centersec.0 .0 as f64) - (radius * (SECTOR_SIZE.0 as f64))) as SpaceUnit;
-    let nsector_x = (1.0 + (2.0 * radius * (SECTOR_SIZE.0 as f64))) as SpaceUnit;
-    let xsecend = ((centersec.0 .1 as f64) + (radius * (SECTOR_SIZE.0 as f64))) as SpaceUnit;
-    debug_assert_eq!(xsecstart + (nsector_x * SECTOR_SIZE.0), xsecend);
-
-    let ysecstart = ((centersec.1 .0 as f64) - (radius * (SECTOR_SIZE.1 as f64))) as SpaceUnit;
-    let nsector_y = (1.0 + (2.0 * radius * (SECTOR_SIZE.1 as f64))) as SpaceUnit;
-    let ysecend = ((centersec.1 .1 as f64) + (radius * (SECTOR_SIZE.1 as f64))) as SpaceUnit;
-    debug_assert_eq!(ysecstart + (nsector_y * SECTOR_SIZE.1), ysecend);
-
-    let zsecstart = ((centersec.2 .0 as f64) - (radius * (SECTOR_SIZE.2 as f64))) as SpaceUnit;
-    let nsector_z = (1.0 + (2.0 * radius * (SECTOR_SIZE.2 as f64))) as SpaceUnit;
-    let zsecend = ((centersec.2 .1 as f64) + (radius * (SECTOR_SIZE.2 as f64))) as SpaceUnit;
-    debug_assert_eq!(zsecstart + (nsector_z * SECTOR_SIZE.2), zsecend);
-
-    for sx in 0..nsector_x {
-        for sy in 0..nsector_y {
-            for sz in 0..nsector_z {
-                sectors.push((
-                    (
-                        xsecstart + (sx * SECTOR_SIZE.0),
-                        xsecstart + ((sx + 1) * SECTOR_SIZE.0),
-                    ),
-                    (
-                        ysecstart + (sy * SECTOR_SIZE.1),
-                        ysecstart + ((sy + 1) * SECTOR_SIZE.1),
-                    ),
-                    (
-                        zsecstart + (sz * SECTOR_SIZE.2),
-                        zsecstart + ((sz + 1) * SECTOR_SIZE.2),
-                    ),
-                ))
+    let lower = |c: SpaceUnit| ((c as f64) - radius).max(0.0) as SpaceUnit;
+    let upper = |c: SpaceUnit| ((c as f64) + radius).min(SpaceUnit::MAX as f64) as SpaceUnit;
+    let start = compute_sector(lower(center.0), lower(center.1), lower(center.2));
+    let end = compute_sector(upper(center.0), upper(center.1), upper(center.2));
+
+    let sector_starts = |from: SpaceUnit, to: SpaceUnit, size: SpaceUnit| {
+        (from as u64..=to as u64).step_by(size as usize)
+    };
+    for sx in sector_starts(start.0 .0, end.0 .0, SECTOR_SIZE.0) {
+        for sy in sector_starts(start.1 .0, end.1 .0, SECTOR_SIZE.1) {
+            for sz in sector_starts(start.2 .0, end.2 .0, SECTOR_SIZE.2) {
+                let sector = compute_sector(sx as SpaceUnit, sy as SpaceUnit, sz as SpaceUnit);
+                if distance_to_sector(center, &sector) <= radius {
+                    sectors.push(sector);
+                }
             }
         }
     }
-
     sectors
 }
 
+// Distance between a point and the closest point of a sector
+fn distance_to_sector(coord: &SpaceCoord, sector: &GalaxySector) -> f64 {
+    let axis = |c: SpaceUnit, (start, end): (SpaceUnit, SpaceUnit)| {
+        if c < start {
+            (start - c) as f64
+        } else if c > end {
+            (c - end) as f64
+        } else {
+            0.0
+        }
+    };
+    let dx = axis(coord.0, sector.0);
+    let dy = axis(coord.1, sector.1);
+    let dz = axis(coord.2, sector.2);
+    (dx.powf(2.0) + dy.powf(2.0) + dz.powf(2.0)).sqrt()
+}
+
 fn get_rand_coord_near(obj: &SpaceCoord, dist: f64, rng: &mut ThreadRng) -> SpaceCoord {
     let theta = rng.random_range(0.0..2.0 * std::f64::consts::PI); // azimuthal angle
     let phi = rng.random_range(0.0..std::f64::consts::PI); // polar angle
@@ -370,3 +392,42 @@ fn test_compute_sector() {
 fn test_heavy_testing() {
     assert!(false);
 }
+
+#[test]
+fn test_sectors_around() {
+    let center = (12500, 12500, 12500);
+    let sectors = sectors_around(&center, 1.0);
+    assert_eq!(sectors, vec![compute_sector(12500, 12500, 12500)]);
+
+    // Reaches the faces of the neighbour sectors, but not their edges or corners
+    let sectors = sectors_around(&center, 2600.0);
+    assert_eq!(sectors.len(), 7);
+
+    let sectors = sectors_around(&(0, 0, 0), 2.0 * (SECTOR_SIZE.0 as f64));
+    assert!(sectors
+        .iter()
+        .all(|s| distance_to_sector(&(0, 0, 0), s) <= 10000.0));
+    assert!(sectors.contains(&compute_sector(0, 0, 0)));
+    assert!(sectors.contains(&compute_sector(9999, 9999, 9999)));
+    assert!(!sectors.contains(&compute_sector(14999, 14999, 0)));
+}
+
+#[test]
+fn test_discover_around() {
+    let mut galaxy = Galaxy::init();
+    let center = (1_000_000, 1_000_000, 1_000_000);
+    assert!(!galaxy.is_discovered(&center));
+    let nsectors = galaxy.discover_around(1, &center);
+    assert_eq!(galaxy.sectors.len(), nsectors);
+    assert_eq!(
+        nsectors,
+        sectors_around(&center, scan::scan_radius(1)).len()
+    );
+    assert!(galaxy.is_discovered(&center));
+
+    // Nothing new to discover on the same spot
+    assert_eq!(galaxy.discover_around(1, &center), 0);
+    assert_eq!(galaxy.sectors.len(), nsectors);
+    let nobjects = galaxy.sectors.values().map(|s| s.len()).sum::<usize>();
+    assert!(nobjects <= nsectors * PLANETS_PER_SECTOR);
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::ship::resources::Resource;
+
+// How much the prices of a station can differ from the others
+const BASE_RATE_SPREAD: f64 = 0.15;
+const RESOURCE_RATE_SPREAD: f64 = 0.08;
+
+// Demand drifts randomly, between these bounds
+const DEMAND_MIN: f64 = 0.8;
+const DEMAND_MAX: f64 = 1.25;
+const DEMAND_DRIFT: f64 = 0.03;
+
+// Buying 50000 worth of equipment in a station raises its price there by 10%
+const PURCHASE_RATE_DIV: f64 = 500000.0;
+const PURCHASE_RATE_MAX: f64 = 0.5;
+// Recent purchases are forgotten by half every 5 minutes of game
+const PURCHASE_HALF_LIFE_SEC: f64 = 300.0;
+// Some shipyards are known to have rarer ships than others
+const SHIPYARD_QUALITY_MAX: f64 = 1.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumIter)]
+pub enum EquipmentKind {
+    Ship,
+    Module,
+    Upgrade,
+}
+
+// Local economy of a station, makes the prices vary from one station to another
+#[derive(Serialize, Debug, Clone)]
+pub struct StationEconomy {
+    // Multiplier of the market price when trading a resource here
+    pub resource_rates: BTreeMap<Resource, f64>,
+    // Multiplier on the equipment prices, specific to this station
+    pub base_rates: BTreeMap<EquipmentKind, f64>,
+    pub demand: BTreeMap<EquipmentKind, f64>,
+    // Money spent recently on equipment in this station
+    pub recent_purchases: BTreeMap<EquipmentKind, f64>,
+    // Makes the rare ships more common in the shipyard
+    pub shipyard_quality: f64,
+}
+
+impl StationEconomy {
+    pub fn random<R: Rng>(rng: &mut R) -> StationEconomy {
+        let resource_rates: BTreeMap<Resource, f64> = Resource::iter()
+            .map(|r| {
+                let rate = 1.0 + rng.random_range(-RESOURCE_RATE_SPREAD..RESOURCE_RATE_SPREAD);
+                (r, rate)
+            })
+            .collect();
+        let mut base_rates: BTreeMap<EquipmentKind, f64> = EquipmentKind::iter()
+            .map(|k| {
+                (
+                    k,
+                    1.0 + rng.random_range(-BASE_RATE_SPREAD..BASE_RATE_SPREAD),
+                )
+            })
+            .collect();
+
+        // Ships are built from the local resources, their price follows them
+        let res_avg = resource_rates.values().sum::<f64>() / (resource_rates.len() as f64);
+        let ship_rate = base_rates.get_mut(&EquipmentKind::Ship).unwrap();
+        *ship_rate = (*ship_rate + res_avg) / 2.0;
+        StationEconomy {
+            resource_rates,
+            base_rates,
+            demand: EquipmentKind::iter().map(|k| (k, 1.0)).collect(),
+            recent_purchases: EquipmentKind::iter().map(|k| (k, 0.0)).collect(),
+            shipyard_quality: rng.random_range(0.0..SHIPYARD_QUALITY_MAX),
+        }
+    }
+
+    pub fn resource_rate(&self, resource: &Resource) -> f64 {
+        self.resource_rates.get(resource).copied().unwrap_or(1.0)
+    }
+
+    // Multiplier on the price of this kind of equipment in this station
+    pub fn price_rate(&self, kind: EquipmentKind) -> f64 {
+        let base = self.base_rates.get(&kind).copied().unwrap_or(1.0);
+        let demand = self.demand.get(&kind).copied().unwrap_or(1.0);
+        let purchases = self.recent_purchases.get(&kind).copied().unwrap_or(0.0);
+        base * demand * (1.0 + (purchases / PURCHASE_RATE_DIV).min(PURCHASE_RATE_MAX))
+    }
+
+    pub fn price_rates(&self) -> BTreeMap<EquipmentKind, f64> {
+        EquipmentKind::iter()
+            .map(|k| (k, self.price_rate(k)))
+            .collect()
+    }
+
+    pub fn record_purchase(&mut self, kind: EquipmentKind, price: f64) {
+        *self.recent_purchases.entry(kind).or_default() += price;
+    }
+
+    // Called regularly, tdelta is the game time since the last update
+    pub fn update<R: Rng>(&mut self, rng: &mut R, tdelta: f64) {
+        let decay = 0.5f64.powf(tdelta / PURCHASE_HALF_LIFE_SEC);
+        for amnt in self.recent_purchases.values_mut() {
+            *amnt *= decay;
+        }
+        for demand in self.demand.values_mut() {
+            let drift = rng.random_range(-DEMAND_DRIFT..DEMAND_DRIFT);
+            *demand = (*demand + drift).clamp(DEMAND_MIN, DEMAND_MAX);
+        }
+    }
+}
+
+#[test]
+fn test_purchases_raise_prices() {
+    let mut rng = rand::rng();
+    let mut economy = StationEconomy::random(&mut rng);
+    let before = economy.price_rate(EquipmentKind::Ship);
+    economy.record_purchase(EquipmentKind::Ship, 50000.0);
+    let after = economy.price_rate(EquipmentKind::Ship);
+    assert!((after / before - 1.1).abs() < 1e-9);
+
+    economy
+        .recent_purchases
+        .insert(EquipmentKind::Ship, 50000.0);
+    economy.update(&mut rng, PURCHASE_HALF_LIFE_SEC);
+    assert!((economy.recent_purchases[&EquipmentKind::Ship] - 25000.0).abs() < 1e-6);
+    assert!(economy.demand[&EquipmentKind::Ship] >= DEMAND_MIN);
+    assert!(economy.demand[&EquipmentKind::Ship] <= DEMAND_MAX);
+}
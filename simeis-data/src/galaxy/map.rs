@@ -0,0 +1,59 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Deref;
+
+use super::planet::PlanetInfo;
+use super::scan::ScanResult;
+use super::station::{StationId, StationInfo};
+use super::{compute_sector, Galaxy, GalaxySector, SpaceCoord, SpaceObject};
+
+// Everything a player discovered from the galaxy, by scanning or visiting it
+#[derive(Debug, Default)]
+pub struct KnownMap {
+    pub sectors: BTreeSet<GalaxySector>,
+    pub planets: BTreeMap<SpaceCoord, PlanetInfo>,
+    pub stations: BTreeMap<StationId, StationInfo>,
+}
+
+impl KnownMap {
+    // Keeps the latest informations we got on every object
+    pub fn learn(&mut self, results: &ScanResult) {
+        self.sectors.extend(results.sectors.iter().cloned());
+        for planet in results.planets.iter() {
+            self.planets.insert(planet.position, planet.clone());
+        }
+        for station in results.stations.iter() {
+            self.stations.insert(station.id, station.clone());
+        }
+    }
+
+    // Only the object located at these exact coordinates is revealed by a visit
+    pub async fn visit(&mut self, galaxy: &Galaxy, coord: &SpaceCoord) {
+        if !galaxy.is_discovered(coord) {
+            return;
+        }
+        self.sectors
+            .insert(compute_sector(coord.0, coord.1, coord.2));
+        match galaxy.get(coord) {
+            Some(SpaceObject::Planet(planet)) => {
+                self.planets
+                    .entry(*coord)
+                    .or_insert_with(|| PlanetInfo::scan(1, planet.as_ref()));
+            }
+            Some(SpaceObject::BaseStation(station)) => {
+                let station = station.read().await; // OK
+                self.stations
+                    .entry(station.id)
+                    .or_insert_with(|| StationInfo::scan(1, station.deref()));
+            }
+            None => {}
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "sectors": self.sectors,
+            "planets": self.planets.values().collect::<Vec<&PlanetInfo>>(),
+            "stations": self.stations.values().collect::<Vec<&StationInfo>>(),
+        })
+    }
+}
@@ -1,23 +1,39 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use strum::IntoEnumIterator;
 
 use crate::ship::resources::Resource;
 
 use super::SpaceCoord;
 
+const SCAN_RANK_RESOURCES: u8 = 2;
+
 // Informations that can be scanned from a planet
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct PlanetInfo {
     pub position: SpaceCoord,
     pub temperature: u16,
     pub solid: bool,
+    pub resources: Option<BTreeMap<Resource, f64>>,
 }
 
 impl PlanetInfo {
-    pub fn scan(_rank: u8, planet: &Planet) -> PlanetInfo {
+    pub fn scan(rank: u8, planet: &Planet) -> PlanetInfo {
+        let resources = if rank >= SCAN_RANK_RESOURCES {
+            let densities = Resource::iter()
+                .map(|r| (r, planet.resource_density(&r)))
+                .filter(|(_, d)| *d > 0.0)
+                .collect();
+            Some(densities)
+        } else {
+            None
+        };
+
         PlanetInfo {
             position: planet.position,
             temperature: planet.temperature,
             solid: planet.solid,
+            resources,
         }
     }
 }
@@ -4,12 +4,27 @@ use serde::{Deserialize, Serialize};
 
 use super::planet::PlanetInfo;
 use super::station::StationInfo;
-use super::{get_distance, SpaceCoord, SpaceObject};
+use super::{get_distance, GalaxySector, SpaceCoord, SpaceObject};
+use crate::ship::ShipInfo;
+
+const SCAN_BASE_RADIUS: f64 = 5000.0;
+const SCAN_RADIUS_RANK_POWF: f64 = 0.75;
+
+// Radius of the sphere covered by a scanner of this rank
+#[inline]
+pub fn scan_radius(rank: u8) -> f64 {
+    SCAN_BASE_RADIUS * (rank.max(1) as f64).powf(SCAN_RADIUS_RANK_POWF)
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ScanResult {
     pub planets: Vec<PlanetInfo>,
     pub stations: Vec<StationInfo>,
+    pub ships: Vec<ShipInfo>,
+    pub first_discovered: usize,
+
+    #[serde(skip)]
+    pub sectors: Vec<GalaxySector>,
 }
 
 impl ScanResult {
@@ -17,6 +32,9 @@ impl ScanResult {
         ScanResult {
             planets: vec![],
             stations: vec![],
+            ships: vec![],
+            first_discovered: 0,
+            sectors: vec![],
         }
     }
 
@@ -32,6 +50,17 @@ impl ScanResult {
         }
     }
 
+    pub fn add_ships(&mut self, rank: u8, center: &SpaceCoord, radius: f64, ships: &[ShipInfo]) {
+        for ship in ships.iter() {
+            if get_distance(center, &ship.position) > radius {
+                continue;
+            }
+            if let Some(info) = ship.scan(rank) {
+                self.ships.push(info);
+            }
+        }
+    }
+
     pub fn get_closest_planet(&self, pos: &SpaceCoord) -> Option<PlanetInfo> {
         let mut planets = self.planets.clone();
         planets.sort_by(|a, b| {
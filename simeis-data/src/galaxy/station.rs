@@ -1,15 +1,19 @@
 use serde::{Deserialize, Serialize};
 
+use crate::achievement::Achievement;
+use crate::crew::recruitment::{self, Candidate};
 use crate::crew::{Crew, CrewId, CrewMemberType};
 use crate::errors::Errcode;
 use crate::market::{Market, MarketTx};
-use crate::player::Player;
+use crate::player::{Player, PlayerId};
 use crate::ship::cargo::ShipCargo;
-use crate::ship::module::ShipModuleId;
+use crate::ship::generation::{self, shipyard_quality};
+use crate::ship::module::{ShipModule, ShipModuleId, ShipModuleType};
 use crate::ship::resources::Resource;
 use crate::ship::upgrade::ShipUpgrade;
-use crate::ship::Ship;
+use crate::ship::{Ship, ShipInfo, ShipState};
 
+use super::economy::{EquipmentKind, StationEconomy};
 use super::scan::ScanResult;
 use super::{Galaxy, SpaceCoord};
 
@@ -18,21 +22,38 @@ const CARGO_BASE_PRICE: f64 = 2.0;
 const CARGO_PRICE_INCDIV: f64 = 1000.0;
 const STATION_INIT_CARGO: f64 = 1000.0;
 
+const SCANNER_BASE_PRICE: f64 = 9000.0;
+const SCANNER_PRICE_RANK_POWF: f64 = 1.8;
+const SCANNER_MAX_RANK: u8 = 10;
+
+// Part of the price of a ship given back when selling it
+pub const SHIP_RESALE_RATE: f64 = 0.6;
+const MODULE_RESALE_RATE: f64 = 0.5;
+
+const SCAN_RANK_OWNER: u8 = 2;
+const SCAN_RANK_CREW: u8 = 4;
+const SCAN_RANK_CARGO: u8 = 5;
+
 pub type StationId = u16;
 
 // TO DO (#43) Add refineries to create fuel & hull plate from raw resources
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct StationInfo {
     pub id: StationId,
     pub position: SpaceCoord,
+    pub owner: Option<PlayerId>,
+    pub crew: Option<Crew>,
+    pub cargo: Option<ShipCargo>,
 }
 
 impl StationInfo {
-    // TO DO (#27) Based on the scanner rank, get informations on crew and cargo
-    pub fn scan(_rank: u8, station: &Station) -> StationInfo {
+    pub fn scan(rank: u8, station: &Station) -> StationInfo {
         StationInfo {
             id: station.id,
             position: station.position,
+            owner: (rank >= SCAN_RANK_OWNER).then_some(station.owner),
+            crew: (rank >= SCAN_RANK_CREW).then(|| station.crew.clone()),
+            cargo: (rank >= SCAN_RANK_CARGO).then(|| station.cargo.clone()),
         }
     }
 }
@@ -40,39 +61,78 @@ impl StationInfo {
 #[derive(Debug)]
 pub struct Station {
     pub id: StationId,
+    pub owner: PlayerId,
     pub position: SpaceCoord,
+    pub scanner_rank: u8,
 
     pub idle_crew: Crew,
     pub crew: Crew,
     pub shipyard: Vec<Ship>,
+    pub candidates: Vec<Candidate>,
     pub cargo: ShipCargo,
     pub trader: Option<CrewId>,
+    pub economy: StationEconomy,
 }
 
 impl Station {
-    pub fn init(id: u16, position: super::SpaceCoord) -> Station {
+    pub fn init(id: u16, owner: PlayerId, position: super::SpaceCoord) -> Station {
+        let mut rng = rand::rng();
+        let economy = StationEconomy::random(&mut rng);
+        let quality = shipyard_quality(economy.shipyard_quality, 0);
         Station {
             id,
+            owner,
             position,
+            scanner_rank: 1,
             idle_crew: Crew::default(),
             crew: Crew::default(),
-            shipyard: Ship::init_shipyard(position),
+            shipyard: generation::shipyard_offers(&mut rng, position, quality),
+            candidates: recruitment::candidates_pool(&mut rng),
             cargo: ShipCargo::with_capacity(STATION_INIT_CARGO),
             trader: None,
+            economy,
         }
     }
 
-    // TO DO (#27) Allow to build improvements for the scanner
-    pub async fn scan(&self, galaxy: &Galaxy) -> ScanResult {
-        galaxy.scan_sector(1, &self.position).await
+    pub async fn scan(&self, galaxy: &Galaxy, ships: &[ShipInfo]) -> ScanResult {
+        galaxy
+            .scan_sector(self.scanner_rank, &self.position, ships)
+            .await
+    }
+
+    pub fn scanner_price(&self, inflation: f64) -> f64 {
+        SCANNER_BASE_PRICE * (self.scanner_rank as f64).powf(SCANNER_PRICE_RANK_POWF) * inflation
+    }
+
+    pub fn buy_scanner_upgrade(
+        &mut self,
+        player: &mut Player,
+        inflation: f64,
+    ) -> Result<(f64, u8), Errcode> {
+        if self.scanner_rank >= SCANNER_MAX_RANK {
+            return Err(Errcode::MaxRankReached(SCANNER_MAX_RANK));
+        }
+        let price = self.scanner_price(inflation);
+        if price > player.money {
+            return Err(Errcode::NotEnoughMoney(player.money, price));
+        }
+        player.money -= price;
+        self.scanner_rank += 1;
+        Ok((price, self.scanner_rank))
     }
 
-    pub fn cargo_price(&self) -> f64 {
-        CARGO_BASE_PRICE.powf((self.cargo.capacity - STATION_INIT_CARGO) / CARGO_PRICE_INCDIV)
+    pub fn cargo_price(&self, inflation: f64) -> f64 {
+        let exp = (self.cargo.capacity - STATION_INIT_CARGO) / CARGO_PRICE_INCDIV;
+        CARGO_BASE_PRICE.powf(exp) * inflation
     }
 
-    pub fn buy_cargo(&mut self, player: &mut Player, amnt: &usize) -> Result<&ShipCargo, Errcode> {
-        let cost = (*amnt as f64) * self.cargo_price();
+    pub fn buy_cargo(
+        &mut self,
+        player: &mut Player,
+        amnt: &usize,
+        inflation: f64,
+    ) -> Result<&ShipCargo, Errcode> {
+        let cost = (*amnt as f64) * self.cargo_price(inflation);
         if cost > player.money {
             return Err(Errcode::NotEnoughMoney(player.money, cost));
         }
@@ -82,7 +142,32 @@ impl Station {
         Ok(&self.cargo)
     }
 
+    // Replaces all the candidates waiting to be hired
+    pub fn refresh_candidates<R: rand::Rng>(&mut self, rng: &mut R) {
+        self.candidates = recruitment::candidates_pool(rng);
+    }
+
+    // Rank of the trader of the station, 0 if there is none
+    pub fn trader_rank(&self) -> u8 {
+        self.trader
+            .and_then(|id| self.crew.0.get(&id))
+            .map(|cm| cm.rank)
+            .unwrap_or(0)
+    }
+
+    pub fn offers_quality(&self) -> f64 {
+        shipyard_quality(self.economy.shipyard_quality, self.trader_rank())
+    }
+
+    // Replaces all the ships on sale by new ones
+    pub fn refresh_shipyard<R: rand::Rng>(&mut self, rng: &mut R) {
+        self.shipyard = generation::shipyard_offers(rng, self.position, self.offers_quality());
+    }
+
     pub fn assign_trader(&mut self, id: CrewId) -> Result<(), Errcode> {
+        if self.trader.is_some() {
+            return Err(Errcode::CrewNotNeeded);
+        }
         let Some(cm) = self.idle_crew.0.remove(&id) else {
             return Err(Errcode::CrewMemberNotIdle(id));
         };
@@ -92,6 +177,30 @@ impl Station {
         Ok(())
     }
 
+    pub fn unassign_trader(&mut self) -> Result<CrewId, Errcode> {
+        let Some(id) = self.trader.take() else {
+            return Err(Errcode::NoTraderAssigned);
+        };
+        let cm = self.crew.0.remove(&id).unwrap();
+        self.idle_crew.0.insert(id, cm);
+        Ok(id)
+    }
+
+    // Brings a crew member of a docked ship back to the idle crew of the station
+    pub fn unboard_crew(&mut self, id: CrewId, ship: &mut Ship) -> Result<(), Errcode> {
+        if self.position != ship.position {
+            return Err(Errcode::ShipNotInStation);
+        }
+        if !matches!(ship.state, ShipState::Idle) {
+            return Err(Errcode::ShipNotIdle);
+        }
+        let Some(cm) = ship.remove_crew_member(&id) else {
+            return Err(Errcode::CrewMemberNotFound(id));
+        };
+        self.idle_crew.0.insert(id, cm);
+        Ok(())
+    }
+
     pub fn onboard_pilot(&mut self, id: CrewId, ship: &mut Ship) -> Result<(), Errcode> {
         let Some(cm) = self.idle_crew.0.get(&id) else {
             return Err(Errcode::CrewMemberNotIdle(id));
@@ -156,9 +265,10 @@ impl Station {
             return Err(Errcode::BuyNothing);
         }
 
-        let tx = market.buy(cm, resource, amnt);
+        let tx = market.buy(cm, resource, amnt, self.economy.resource_rate(resource));
+        self.crew.0.get_mut(&trader).unwrap().gain_xp(amnt);
         player.money -= tx.removed_money.unwrap();
-        player.score -= tx.removed_money.unwrap();
+        player.trade_profit -= tx.removed_money.unwrap();
         let (r, a) = tx.added_cargo.unwrap();
         self.cargo.add_resource(&r, a);
         Ok(tx)
@@ -183,9 +293,11 @@ impl Station {
             return Err(Errcode::SellNothing);
         }
 
-        let tx = market.sell(cm, resource, amnt);
+        let tx = market.sell(cm, resource, amnt, self.economy.resource_rate(resource));
+        self.crew.0.get_mut(&trader).unwrap().gain_xp(amnt);
         player.money += tx.added_money.unwrap();
-        player.score += tx.added_money.unwrap();
+        player.trade_profit += tx.added_money.unwrap();
+        player.unlock(Achievement::FirstSale);
         let (r, a) = tx.removed_cargo.unwrap();
         let unloaded = self.cargo.unload(&r, a);
         debug_assert_eq!(unloaded, a);
@@ -237,14 +349,35 @@ impl Station {
         );
         debug_assert!(ship.hull_decay >= 0.0, "{}", ship.hull_decay);
         debug_assert!(unloaded >= 0.0, "{}", unloaded);
+        ship.update_perf_stats();
         Ok(unloaded)
     }
 
-    pub fn get_ship_upgrade_price(&self, upgrade: &ShipUpgrade) -> f64 {
-        // TO DO (#22) Modify price based on station economy metrics
-        upgrade.get_price()
+    pub fn get_ship_upgrade_price(&self, upgrade: &ShipUpgrade, inflation: f64) -> f64 {
+        upgrade.get_price(inflation) * self.economy.price_rate(EquipmentKind::Upgrade)
+    }
+
+    pub fn ship_price(&self, ship: &Ship, inflation: f64) -> f64 {
+        ship.compute_price(inflation)
+            * ship.hull_value_factor()
+            * self.economy.price_rate(EquipmentKind::Ship)
     }
-}
 
-// TO DO (#22)    Have a "ship price rate" metric for a station, that afffects the ship prices
-//     Correlated to the price of the resources on the station
+    pub fn module_resale_price(&self, module: &ShipModule, inflation: f64) -> f64 {
+        module.value(inflation)
+            * self.economy.price_rate(EquipmentKind::Module)
+            * MODULE_RESALE_RATE
+    }
+
+    pub fn ship_resale_price(&self, ship: &Ship, inflation: f64) -> f64 {
+        self.ship_price(ship, inflation) * SHIP_RESALE_RATE
+    }
+
+    pub fn module_price(&self, modtype: &ShipModuleType, inflation: f64) -> f64 {
+        modtype.get_price_buy(inflation) * self.economy.price_rate(EquipmentKind::Module)
+    }
+
+    pub fn module_upgrade_price(&self, module: &ShipModule, inflation: f64) -> f64 {
+        module.price_next_rank(inflation) * self.economy.price_rate(EquipmentKind::Module)
+    }
+}
@@ -1,33 +1,135 @@
 use base64::{prelude::BASE64_STANDARD, Engine};
 use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use tokio::sync::mpsc::error::TryRecvError;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 
-#[cfg(not(feature = "testing"))]
-use tokio::sync::mpsc::error::TryRecvError;
-
 use rand::{Rng, SeedableRng};
 
+use crate::achievement::{Achievement, MILLIONAIRE_MONEY};
+use crate::config::GameConfig;
+use crate::crew::recruitment::CANDIDATES_REFRESH_SEC;
 use crate::errors::Errcode;
 use crate::galaxy::Galaxy;
 use crate::market::{Market, MARKET_CHANGE_SEC};
+use crate::metrics::GameMetrics;
 use crate::player::{Player, PlayerId, PlayerKey};
-use crate::ship::ShipState;
-use crate::syslog::{SyslogEvent, SyslogFifo, SyslogRecv, SyslogSend};
+use crate::score::{Score, SCORE_SNAPSHOT_SEC};
+use crate::ship::generation::SHIPYARD_REFRESH_SEC;
+use crate::ship::navigation::FlightStatus;
+use crate::ship::resources::Resource;
+use crate::ship::{ShipInfo, ShipState};
+use crate::syslog::{ArrivalLocation, SyslogEvent, SyslogFifo, SyslogRecv, SyslogSend};
 
-const ITER_PERIOD: Duration = Duration::from_millis(20);
-
-// TO DO (#23) Have a global "inflation" rate for all users, that increases over time
-//     Equipment becomes more and more expansive
+// If the game thread got late, at most this number of ticks is simulated at once
+const MAX_TICK_CATCHUP: u32 = 10;
+// Game seconds between two updates of the economy of the stations
+const ECONOMY_UPDATE_SEC: f64 = 10.0;
 
 pub enum GameSignal {
     Stop,
     Tick,
+    Pause,
+    Resume,
+    Export,
 }
 
+// Simulation clock of the game, can be read without locking anything
+pub struct GameClock {
+    period: Duration,
+    scale: f64,
+    tick: AtomicU64,
+    // Seconds of game simulated, as f64 bits
+    game_time: AtomicU64,
+    paused: AtomicBool,
+    manual: AtomicBool,
+}
+
+impl GameClock {
+    pub fn new(config: &GameConfig) -> GameClock {
+        GameClock {
+            period: config.tick_period,
+            scale: config.time_scale,
+            tick: AtomicU64::new(0),
+            game_time: AtomicU64::new(0.0f64.to_bits()),
+            paused: AtomicBool::new(false),
+            manual: AtomicBool::new(config.manual_ticks),
+        }
+    }
+
+    #[inline]
+    pub fn tick(&self) -> u64 {
+        self.tick.load(Ordering::Relaxed)
+    }
+
+    // Only written by the game thread
+    pub fn game_time(&self) -> f64 {
+        f64::from_bits(self.game_time.load(Ordering::Relaxed))
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    // In manual mode, the game only advances on GameSignal::Tick
+    pub fn is_manual(&self) -> bool {
+        self.manual.load(Ordering::Relaxed)
+    }
+
+    pub fn set_manual(&self, manual: bool) {
+        self.manual.store(manual, Ordering::Relaxed);
+    }
+
+    // Game seconds elapsed during this real duration
+    fn game_delta(&self, real: Duration) -> f64 {
+        real.min(self.period * MAX_TICK_CATCHUP).as_secs_f64() * self.scale
+    }
+
+    fn advance(&self, tdelta: f64) -> u64 {
+        let game_time = self.game_time() + tdelta;
+        self.game_time.store(game_time.to_bits(), Ordering::Relaxed);
+        self.tick.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+// Lock hierarchy of the game state
+//
+// A task holding one of these locks can only ask for the ones below it,
+// never for one above (or at the same level), otherwise it may deadlock
+// with another task doing the opposite.
+//
+//  1. Game::player_index
+//  2. Game::players
+//  3. Player, if more than one is needed they are locked by ascending PlayerId
+//  4. Game::galaxy
+//  5. Station, only read locks if more than one is needed
+//  6. Game::market
+//  7. Game::fifo_events, then the player's own fifo
+//
+// Any lock can be skipped, and should be released as soon as possible.
+// Sending a syslog event never waits, it can be done while holding any of them.
+// The metrics counters are never held across an await, they can be taken anytime.
+// Functions taking locks by themselves state which ones must not be held.
 #[derive(Clone)]
 pub struct Game {
     pub players: Arc<RwLock<BTreeMap<PlayerId, Arc<RwLock<Player>>>>>,
@@ -38,10 +140,13 @@ pub struct Game {
     pub fifo_events: SyslogFifo,
     pub tstart: f64,
     pub send_sig: Sender<GameSignal>,
+    pub clock: Arc<GameClock>,
+    pub config: Arc<GameConfig>,
+    pub metrics: Arc<GameMetrics>,
 }
 
 impl Game {
-    pub fn init() -> (JoinHandle<()>, Game) {
+    pub fn init(config: GameConfig) -> (JoinHandle<()>, Game) {
         let (send_stop, recv_stop) = tokio::sync::mpsc::channel(5);
         let (syssend, sysrecv) = SyslogSend::channel();
         let tstart = std::time::SystemTime::now()
@@ -57,6 +162,9 @@ impl Game {
             syslog: syssend.clone(),
             fifo_events: sysrecv.fifo.clone(),
             tstart,
+            clock: Arc::new(GameClock::new(&config)),
+            config: Arc::new(config),
+            metrics: Arc::new(GameMetrics::new(sysrecv.counts.clone())),
         };
 
         let thread_data = data.clone();
@@ -65,38 +173,83 @@ impl Game {
         (thread, data)
     }
 
-    #[allow(unused_variables, unused_mut)]
-    pub async fn start(&self, mut stop: Receiver<GameSignal>, syslog: SyslogRecv) {
+    pub async fn start(&self, mut signals: Receiver<GameSignal>, syslog: SyslogRecv) {
         log::debug!("Started thread");
-        let sleepmin_iter = ITER_PERIOD;
         let mut last_iter = Instant::now();
-        let mut market_last_tick = Instant::now();
+        let mut market_elapsed = 0.0;
+        let mut score_elapsed = 0.0;
+        let mut economy_elapsed = 0.0;
+        let mut shipyard_elapsed = 0.0;
+        let mut candidates_elapsed = 0.0;
         let mut rng = rand::rngs::SmallRng::from_os_rng();
 
         'main: loop {
-            #[cfg(feature = "testing")]
-            let got = stop.recv().await;
-
-            #[cfg(not(feature = "testing"))]
-            let got = match stop.try_recv() {
-                Ok(res) => Some(res),
-                Err(TryRecvError::Empty) => Some(GameSignal::Tick),
-                Err(e) => {
-                    log::error!("Error while getting next tick / stop signal:  {e:?}");
-                    None
+            let got = if self.clock.is_manual() {
+                // Wake up regularly to check if the clock left the manual mode
+                match tokio::time::timeout(self.clock.period(), signals.recv()).await {
+                    Ok(got) => got,
+                    Err(_) => continue 'main,
+                }
+            } else {
+                match signals.try_recv() {
+                    Ok(res) => Some(res),
+                    Err(TryRecvError::Empty) => Some(GameSignal::Tick),
+                    Err(e) => {
+                        log::error!("Error while getting next tick / stop signal:  {e:?}");
+                        None
+                    }
                 }
             };
 
             match got {
                 Some(GameSignal::Tick) => {
-                    self.threadloop(&mut rng, &mut market_last_tick, &syslog)
+                    let tdelta = if self.clock.is_manual() {
+                        self.clock.game_delta(self.clock.period())
+                    } else {
+                        let took = last_iter.elapsed();
+                        tokio::time::sleep(self.clock.period().saturating_sub(took)).await;
+                        let real = last_iter.elapsed();
+                        last_iter = Instant::now();
+                        self.clock.game_delta(real)
+                    };
+
+                    if self.clock.is_paused() {
+                        continue 'main;
+                    }
+                    self.clock.advance(tdelta);
+                    let tloop = Instant::now();
+                    self.threadloop(tdelta, &mut rng, &mut market_elapsed, &syslog)
                         .await;
+                    score_elapsed += tdelta;
+                    if score_elapsed >= SCORE_SNAPSHOT_SEC {
+                        self.update_scores().await;
+                        score_elapsed = 0.0;
+                    }
+                    economy_elapsed += tdelta;
+                    if economy_elapsed >= ECONOMY_UPDATE_SEC {
+                        self.update_economies(&mut rng, economy_elapsed).await;
+                        self.update_morale(economy_elapsed).await;
+                        economy_elapsed = 0.0;
+                    }
+                    shipyard_elapsed += tdelta;
+                    if shipyard_elapsed >= SHIPYARD_REFRESH_SEC {
+                        self.refresh_shipyards(&mut rng).await;
+                        shipyard_elapsed = 0.0;
+                    }
+                    candidates_elapsed += tdelta;
+                    if candidates_elapsed >= CANDIDATES_REFRESH_SEC {
+                        self.refresh_candidates(&mut rng).await;
+                        candidates_elapsed = 0.0;
+                    }
+                    self.metrics
+                        .threadloop_done(tloop.elapsed(), self.clock.period());
+                }
 
-                    #[cfg(not(feature = "testing"))]
-                    {
-                        let took = Instant::now() - last_iter;
-                        tokio::time::sleep(sleepmin_iter.saturating_sub(took)).await;
-                        last_iter = Instant::now();
+                Some(GameSignal::Pause) => self.clock.pause(),
+                Some(GameSignal::Resume) => self.clock.resume(),
+                Some(GameSignal::Export) => {
+                    if let Err(e) = self.export().await {
+                        log::error!("Unable to export the game: {e}");
                     }
                 }
 
@@ -106,43 +259,111 @@ impl Game {
         log::info!("Exiting game thread");
     }
 
-    async fn threadloop<R: Rng>(&self, rng: &mut R, mlt: &mut Instant, syslog: &SyslogRecv) {
-        let market_change_proba = (mlt.elapsed().as_secs_f64() / MARKET_CHANGE_SEC).min(1.0);
+    // Simulates tdelta seconds of game
+    async fn threadloop<R: Rng>(
+        &self,
+        tdelta: f64,
+        rng: &mut R,
+        market_elapsed: &mut f64,
+        syslog: &SyslogRecv,
+    ) {
+        *market_elapsed += tdelta;
+        let market_change_proba = (*market_elapsed / MARKET_CHANGE_SEC).min(1.0);
+        let inflation = self.inflation();
 
-        // OK
-        let players = self.players.read().await;
-        let mut all_players: Vec<PlayerId> = players.keys().cloned().collect();
-        all_players.sort();
-        for player_id in all_players {
-            let mut player = players.get(&player_id).unwrap().write().await; // OK
-            player.update_money(syslog, ITER_PERIOD.as_secs_f64()).await;
+        // Release the player list before updating them, so new players can be created
+        let all_players: Vec<(PlayerId, Arc<RwLock<Player>>)> = self
+            .players
+            .read()
+            .await
+            .iter()
+            .map(|(id, player)| (*id, player.clone()))
+            .collect();
+        let player_ids: Vec<PlayerId> = all_players.iter().map(|(id, _)| *id).collect();
+        for (player_id, player) in all_players {
+            let mut player = player.write().await; // OK
+            player.update_money(syslog, tdelta, inflation).await;
 
             let mut deadship = vec![];
+            let mut visited = vec![];
+            let mut arrived = vec![];
+            let mut extracted_gold = false;
             for (id, ship) in player.ships.iter_mut() {
                 match ship.state {
                     ShipState::InFlight(..) => {
-                        let finished = ship.update_flight(ITER_PERIOD.as_secs_f64());
-                        if finished {
-                            ship.state = ShipState::Idle;
-                            if ship.hull_decay >= ship.hull_decay_capacity {
+                        let wear = ship.hull_wear_ratio();
+                        let status = ship.update_flight(tdelta);
+                        let alive = status != FlightStatus::HullWornOut;
+                        let crossed = ship.hull_threshold_crossed(wear);
+                        if let Some(threshold) = crossed.filter(|_| alive) {
+                            let event = SyslogEvent::HullCritical {
+                                ship: *id,
+                                threshold,
+                                wear: ship.hull_wear_ratio(),
+                            };
+                            syslog.event(player_id, event).await;
+                        }
+                        match status {
+                            FlightStatus::InFlight => {}
+                            FlightStatus::HullWornOut => {
+                                ship.state = ShipState::Idle;
                                 deadship.push(*id);
-                            } else {
-                                syslog
-                                    .event(player_id, SyslogEvent::ShipFlightFinished(*id))
-                                    .await;
+                            }
+                            FlightStatus::OutOfFuel => {
+                                ship.state = ShipState::Idle;
+                                visited.push(ship.position);
+                                let event = SyslogEvent::ShipOutOfFuel {
+                                    ship: *id,
+                                    position: ship.position,
+                                };
+                                syslog.event(player_id, event).await;
+                            }
+                            FlightStatus::Arrived => {
+                                ship.state = ShipState::Idle;
+                                visited.push(ship.position);
+                                arrived.push((*id, ship.position));
                             }
                         }
                     }
 
                     ShipState::Extracting(..) => {
-                        let finished = ship.update_extract(ITER_PERIOD.as_secs_f64());
+                        let finished = ship.update_extract(tdelta);
+                        if ship
+                            .extracted
+                            .get(&Resource::Gold)
+                            .is_some_and(|a| *a > 0.0)
+                        {
+                            extracted_gold = true;
+                        }
+                        if finished {
+                            ship.state = ShipState::Idle;
+                            let event = SyslogEvent::CargoFull {
+                                ship: *id,
+                                extracted: ship.extracted.clone(),
+                            };
+                            syslog.event(player_id, event).await;
+                        }
+                    }
+
+                    ShipState::Repairing(..) => {
+                        let finished = ship.update_repair(tdelta);
                         if finished {
                             ship.state = ShipState::Idle;
                             syslog
-                                .event(player_id, SyslogEvent::ExtractionStopped(*id))
+                                .event(player_id, SyslogEvent::RepairStopped(*id))
                                 .await;
                         }
                     }
+
+                    ShipState::Towed(ref tow) => {
+                        let station = tow.station;
+                        let finished = ship.update_tow(tdelta);
+                        if finished {
+                            ship.state = ShipState::Idle;
+                            let event = SyslogEvent::ShipTowed { ship: *id, station };
+                            syslog.event(player_id, event).await;
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -152,17 +373,148 @@ impl Game {
                     .await;
                 player.ships.remove(&id);
             }
+
+            if !visited.is_empty() {
+                let galaxy = self.galaxy.read().await; // OK
+                for coord in visited {
+                    player.map.visit(&galaxy, &coord).await;
+                }
+                for (ship, position) in arrived {
+                    let location = if galaxy.get_station(&position).await.is_some() {
+                        ArrivalLocation::Station
+                    } else if galaxy.get_planet(&position).await.is_some() {
+                        ArrivalLocation::Planet
+                    } else {
+                        ArrivalLocation::EmptySpace
+                    };
+                    let event = SyslogEvent::ShipArrived {
+                        ship,
+                        position,
+                        location,
+                    };
+                    syslog.event(player_id, event).await;
+                }
+            }
+
+            if extracted_gold {
+                player.unlock(Achievement::FirstGold);
+            }
+            if player.money >= MILLIONAIRE_MONEY {
+                player.unlock(Achievement::Millionaire);
+            }
+            for achievement in player.take_unlocked() {
+                let reward = achievement.reward() * self.config.achievement_rewards;
+                player.money += reward;
+                syslog
+                    .event(
+                        player_id,
+                        SyslogEvent::AchievementUnlocked {
+                            achievement,
+                            reward,
+                        },
+                    )
+                    .await;
+            }
         }
 
         if rng.random_bool(market_change_proba) {
             #[cfg(not(feature = "testing"))]
-            self.market.write().await.update_prices(rng); // OK
-            *mlt = Instant::now();
+            let spikes = self.market.write().await.update_prices(rng); // OK
+            #[cfg(feature = "testing")]
+            let spikes: Vec<(Resource, f64, f64)> = vec![];
+            *market_elapsed = 0.0;
+
+            for (resource, old_price, new_price) in spikes {
+                for id in player_ids.iter() {
+                    let event = SyslogEvent::PriceSpike {
+                        resource,
+                        old_price,
+                        new_price,
+                    };
+                    syslog.event(*id, event).await;
+                }
+            }
         }
 
         syslog.update().await;
     }
 
+    // Must be called without holding any lock
+    async fn update_scores(&self) {
+        let prices = self.market.read().await.prices.clone();
+        let tick = self.clock.tick();
+        let inflation = self.inflation();
+        let all_players: Vec<Arc<RwLock<Player>>> =
+            self.players.read().await.values().cloned().collect();
+        for player in all_players {
+            let mut player = player.write().await; // OK
+            if player.lost {
+                continue;
+            }
+            let galaxy = self.galaxy.read().await; // OK
+            let score = Score::compute(tick, &player, &galaxy, &prices, inflation).await;
+            drop(galaxy);
+            player.score = score.total;
+            player.scores.push(score);
+        }
+    }
+
+    // Must be called without holding any lock
+    async fn update_economies<R: Rng>(&self, rng: &mut R, tdelta: f64) {
+        let stations = self.galaxy.read().await.all_stations();
+        for station in stations {
+            station.write().await.economy.update(rng, tdelta); // OK
+        }
+    }
+
+    // Must be called without holding any lock
+    async fn update_morale(&self, tdelta: f64) {
+        let inflation = self.inflation();
+        let all_players: Vec<Arc<RwLock<Player>>> =
+            self.players.read().await.values().cloned().collect();
+        for player in all_players {
+            let mut player = player.write().await; // OK
+            if player.lost {
+                continue;
+            }
+            let underpaid = player.low_funds(inflation);
+            for ship in player.ships.values_mut() {
+                // The performances of the pilot must not change along a flight
+                if let ShipState::InFlight(..) = ship.state {
+                    continue;
+                }
+                ship.crew.update_morale(tdelta, underpaid);
+                ship.update_perf_stats();
+            }
+
+            let galaxy = self.galaxy.read().await; // OK
+            for coord in player.stations.values() {
+                let Some(station) = galaxy.get_station(coord).await else {
+                    continue;
+                };
+                let mut station = station.write().await; // OK
+                station.crew.update_morale(tdelta, underpaid);
+                station.idle_crew.update_morale(tdelta, underpaid);
+            }
+        }
+    }
+
+    // Must be called without holding any lock
+    async fn refresh_shipyards<R: Rng>(&self, rng: &mut R) {
+        let stations = self.galaxy.read().await.all_stations();
+        for station in stations {
+            station.write().await.refresh_shipyard(rng); // OK
+        }
+    }
+
+    // Must be called without holding any lock
+    async fn refresh_candidates<R: Rng>(&self, rng: &mut R) {
+        let stations = self.galaxy.read().await.all_stations();
+        for station in stations {
+            station.write().await.refresh_candidates(rng); // OK
+        }
+    }
+
     pub async fn stop(self, handle: JoinHandle<()>) {
         log::info!("Asking game thread to exit");
         self.send_sig.send(GameSignal::Stop).await.unwrap();
@@ -170,14 +522,96 @@ impl Game {
         log::info!("Game stopped");
     }
 
+    // Multiplier applied on the price of everything the players buy, and on the wages
+    pub fn inflation(&self) -> f64 {
+        self.config.inflation.index(self.clock.game_time())
+    }
+
+    // Must be called without holding any lock on a player
+    pub async fn ships_in_flight(&self) -> Vec<ShipInfo> {
+        let players = self.players.read().await;
+        let mut ships = vec![];
+        for (pid, player) in players.iter() {
+            let player = player.read().await;
+            for ship in player.ships.values() {
+                if let ShipState::InFlight(_) = ship.state {
+                    ships.push(ShipInfo::new(*pid, ship));
+                }
+            }
+        }
+        ships
+    }
+
+    // Must be called without holding any lock
+    pub async fn snapshot(&self) -> serde_json::Value {
+        let players = self.players.read().await;
+        let mut all_players = vec![];
+        for player in players.values() {
+            let player = player.read().await;
+            all_players.push(player.to_json(true));
+        }
+        drop(players);
+        let market = self.market.read().await;
+        serde_json::json!({
+            "tick": self.clock.tick(),
+            "players": all_players,
+            "market": market.prices,
+        })
+    }
+
+    // Writes a snapshot of the game at the configured export path, it can't be loaded back
+    // Must be called without holding any lock
+    pub async fn export(&self) -> std::io::Result<()> {
+        let data = self.snapshot().await.to_string();
+        let path = self.config.export_path.clone();
+        tokio::task::spawn_blocking(move || std::fs::write(path, data)).await??;
+        log::info!("Game exported to {:?}", self.config.export_path);
+        Ok(())
+    }
+
+    // The player cannot use its key anymore, but its ID stays taken
+    // Must be called without holding any lock
+    pub async fn kick_player(&self, id: PlayerId) -> Result<(), Errcode> {
+        let mut index = self.player_index.write().await;
+        let players = self.players.read().await;
+        let Some(player) = players.get(&id) else {
+            return Err(Errcode::PlayerNotFound(id));
+        };
+        let mut player = player.write().await;
+        index.remove(&player.key);
+        if !player.lost {
+            player.lost = true;
+            self.syslog.event(&id, SyslogEvent::GameLost).await;
+        }
+        Ok(())
+    }
+
+    // Must be called without holding any lock
     pub async fn new_player(&self, name: String) -> Result<(PlayerId, String), Errcode> {
         let mut index = self.player_index.write().await;
         let mut players = self.players.write().await;
+        for (pid, player) in players.iter() {
+            if player.read().await.name == name {
+                return Err(Errcode::PlayerAlreadyExists(*pid, name));
+            }
+        }
+
+        // Two names can have the same hash, take the next free ID
+        let mut pid = Player::compute_id(&name);
+        let mut ntries: usize = 0;
+        while players.contains_key(&pid) {
+            pid = pid.wrapping_add(1);
+            ntries += 1;
+            if ntries > PlayerId::MAX as usize {
+                return Err(Errcode::TooManyPlayers);
+            }
+        }
+
         let mut galaxy = self.galaxy.write().await;
-        let station = galaxy.init_new_station().await;
+        let station = galaxy.init_new_station(pid).await;
+        drop(galaxy);
 
-        let player = Player::new(station, name);
-        let pid = player.id;
+        let player = Player::new(pid, station, name);
         let key = BASE64_STANDARD.encode(player.key);
 
         index.insert(player.key, player.id);
@@ -186,3 +620,45 @@ impl Game {
         Ok((pid, key))
     }
 }
+
+#[test]
+fn test_kick_player() {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    rt.block_on(async {
+        let (handle, game) = Game::init(GameConfig::default());
+        let (pid, _) = game.new_player("kicked".to_string()).await.unwrap();
+        let key = game
+            .players
+            .read()
+            .await
+            .get(&pid)
+            .unwrap()
+            .read()
+            .await
+            .key;
+        assert!(game.player_index.read().await.contains_key(&key));
+
+        game.kick_player(pid).await.unwrap();
+        assert!(!game.player_index.read().await.contains_key(&key));
+        assert!(
+            game.players
+                .read()
+                .await
+                .get(&pid)
+                .unwrap()
+                .read()
+                .await
+                .lost
+        );
+        assert!(game.new_player("kicked".to_string()).await.is_err());
+        assert!(game.kick_player(pid.wrapping_add(1)).await.is_err());
+
+        let snapshot = game.snapshot().await;
+        assert_eq!(snapshot["players"][0]["lost"], true);
+        game.stop(handle).await;
+    });
+}
@@ -1,11 +1,15 @@
 #![allow(dead_code)]
 
+pub mod achievement;
+pub mod config;
 pub mod crew;
 pub mod errors;
 pub mod galaxy;
 pub mod game;
 pub mod market;
+pub mod metrics;
 pub mod player;
+pub mod score;
 pub mod ship;
 pub mod syslog;
 
@@ -2,9 +2,11 @@ use rand::Rng;
 use rand_distr::{Distribution, Normal};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::str::FromStr;
 use strum::IntoEnumIterator;
 
-use crate::{crew::CrewMember, ship::resources::Resource};
+use crate::crew::{CrewMember, CrewTrait};
+use crate::ship::resources::Resource;
 
 const MAX_AVG_AMPL: f64 = 5.0 / 100.0;
 const STD_DIV: f64 = 1.5;
@@ -18,11 +20,21 @@ const PRICE_INC_DIV: f64 = 40000.0;
 const PRICE_INC_RANGE_MAX: f64 = 10.0 / 100.0;
 const PRICE_INC_MIN_RATIO: f64 = 75.0 / 100.0;
 
+const INFLATION_PERIOD_SEC: f64 = 3600.0;
+// A price changing more than this in a single update is reported to the players
+const PRICE_SPIKE_RATIO: f64 = 10.0 / 100.0;
+
 #[inline]
 pub fn fee_rate(rank: u8) -> f64 {
     BASE_FEE_RATE / (rank as f64).powf(FEE_RATE_DEC_POWF)
 }
 
+// The experience, the morale and the traits of the trader change the fees
+#[inline]
+pub fn trader_fee_rate(trader: &CrewMember) -> f64 {
+    fee_rate(trader.rank) * trader.trait_factor(CrewTrait::fee_factor) / trader.performance()
+}
+
 #[derive(Serialize)]
 pub struct Market {
     pub prices: BTreeMap<Resource, f64>,
@@ -54,7 +66,9 @@ impl Market {
         old * (1.0 + change)
     }
 
-    pub fn update_prices<R: Rng>(&mut self, rng: &mut R) {
+    // Returns the resources whose price spiked, with their old and new price
+    pub fn update_prices<R: Rng>(&mut self, rng: &mut R) -> Vec<(Resource, f64, f64)> {
+        let mut spikes = vec![];
         let mut new_prices = vec![];
         for (res, price) in self.prices.iter() {
             if !rng.random_bool(UPD_PRICE_PROBA) {
@@ -67,15 +81,26 @@ impl Market {
         for (r, price) in new_prices {
             let p = self.prices.get_mut(&r).unwrap();
             log::debug!("{r:?} {price} ({:?}%)", (price / r.base_price()) * 100.0);
+            if ((price / *p) - 1.0).abs() >= PRICE_SPIKE_RATIO {
+                spikes.push((r, *p, price));
+            }
             *p = price;
         }
+        spikes
     }
 
-    pub fn buy(&mut self, trader: &CrewMember, r: &Resource, amnt: f64) -> MarketTx {
+    // The local rate is applied by the station where the transaction is made
+    pub fn buy(
+        &mut self,
+        trader: &CrewMember,
+        r: &Resource,
+        amnt: f64,
+        local_rate: f64,
+    ) -> MarketTx {
         assert!(amnt > 0.0);
-        let fee_rate = fee_rate(trader.rank);
+        let fee_rate = trader_fee_rate(trader);
 
-        let price = *self.prices.get(r).unwrap();
+        let price = *self.prices.get(r).unwrap() * local_rate;
         assert!(price > 0.0);
         let cost = amnt * price;
         let fees = cost * fee_rate;
@@ -93,11 +118,17 @@ impl Market {
         }
     }
 
-    pub fn sell(&mut self, trader: &CrewMember, r: &Resource, amnt: f64) -> MarketTx {
+    pub fn sell(
+        &mut self,
+        trader: &CrewMember,
+        r: &Resource,
+        amnt: f64,
+        local_rate: f64,
+    ) -> MarketTx {
         assert!(amnt > 0.0);
-        let fee_rate = fee_rate(trader.rank);
+        let fee_rate = trader_fee_rate(trader);
 
-        let price = *self.prices.get(r).unwrap();
+        let price = *self.prices.get(r).unwrap() * local_rate;
         assert!(price > 0.0);
         let cost = amnt * price;
         let fees = cost * fee_rate;
@@ -117,7 +148,49 @@ impl Market {
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+// How the equipment becomes more expensive with the time
+// The rate is the inflation for each hour of game
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum InflationCurve {
+    Flat,
+    Linear(f64),
+    Exponential(f64),
+}
+
+impl InflationCurve {
+    // Multiplier to apply on the prices, after this number of seconds of game
+    pub fn index(&self, game_secs: f64) -> f64 {
+        let periods = game_secs / INFLATION_PERIOD_SEC;
+        match self {
+            InflationCurve::Flat => 1.0,
+            InflationCurve::Linear(rate) => 1.0 + (rate * periods),
+            InflationCurve::Exponential(rate) => (1.0 + rate).powf(periods),
+        }
+    }
+}
+
+// Parsed from "flat", "linear:<rate>" or "exponential:<rate>"
+impl FromStr for InflationCurve {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (curve, rate) = match s.split_once(':') {
+            Some((curve, rate)) => (curve, rate.parse::<f64>().map_err(|_| ())?),
+            None => (s, 0.0),
+        };
+        if !rate.is_finite() || rate < 0.0 {
+            return Err(());
+        }
+        match curve.to_lowercase().as_str() {
+            "flat" => Ok(InflationCurve::Flat),
+            "linear" => Ok(InflationCurve::Linear(rate)),
+            "exponential" => Ok(InflationCurve::Exponential(rate)),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct MarketTx {
     pub added_cargo: Option<(Resource, f64)>,
     pub removed_cargo: Option<(Resource, f64)>,
@@ -126,3 +199,52 @@ pub struct MarketTx {
     pub removed_money: Option<f64>,
     pub fees: f64,
 }
+
+#[test]
+fn test_inflation_curve() {
+    assert_eq!(InflationCurve::from_str("flat"), Ok(InflationCurve::Flat));
+    assert_eq!(
+        InflationCurve::from_str("linear:0.1"),
+        Ok(InflationCurve::Linear(0.1))
+    );
+    assert!(InflationCurve::from_str("exponential:-1").is_err());
+    assert!(InflationCurve::from_str("quadratic:1").is_err());
+
+    for curve in [
+        InflationCurve::Flat,
+        InflationCurve::Linear(0.05),
+        InflationCurve::Exponential(0.05),
+    ] {
+        assert_eq!(curve.index(0.0), 1.0);
+        let mut last = 1.0;
+        for t in 1..100 {
+            let index = curve.index((t * 600) as f64);
+            assert!(index >= last);
+            last = index;
+        }
+    }
+    assert!((InflationCurve::Linear(0.1).index(7200.0) - 1.2).abs() < 1e-9);
+    assert!((InflationCurve::Exponential(0.1).index(7200.0) - 1.21).abs() < 1e-9);
+}
+
+#[test]
+fn test_fee_rate_curve() {
+    let max = crate::crew::CrewMemberType::Trader.max_rank();
+    assert_eq!(fee_rate(1), BASE_FEE_RATE);
+    for rank in 2..=max {
+        let (fee, prev) = (fee_rate(rank), fee_rate(rank - 1));
+        assert!(fee > 0.0 && fee < prev);
+        if rank > 2 {
+            assert!(prev - fee < fee_rate(rank - 2) - prev);
+        }
+    }
+
+    crate::tests::create_property_based_test(1000, &[], |rng| {
+        let mut trader = crate::crew::recruitment::Candidate::random(rng).member;
+        trader.member_type = crate::crew::CrewMemberType::Trader;
+        trader.rank = rng.random_range(1..=max);
+        trader.morale = rng.random_range(0.0..=1.0);
+        let fee = trader_fee_rate(&trader);
+        assert!(fee > 0.0 && fee < 1.0);
+    });
+}
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use strum::VariantNames;
+
+use crate::game::Game;
+use crate::ship::ShipState;
+
+pub type EventCounts = Arc<Mutex<BTreeMap<&'static str, u64>>>;
+
+// Collected by the game thread, rendered on demand
+#[derive(Default)]
+pub struct GameMetrics {
+    threadloop_last_ns: AtomicU64,
+    threadloop_sum_ns: AtomicU64,
+    threadloop_count: AtomicU64,
+    threadloop_overrun: AtomicU64,
+    pub(crate) syslog_events: EventCounts,
+}
+
+impl GameMetrics {
+    pub fn new(syslog_events: EventCounts) -> GameMetrics {
+        GameMetrics {
+            syslog_events,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn threadloop_done(&self, took: Duration, period: Duration) {
+        let ns = took.as_nanos() as u64;
+        self.threadloop_last_ns.store(ns, Ordering::Relaxed);
+        self.threadloop_sum_ns.fetch_add(ns, Ordering::Relaxed);
+        self.threadloop_count.fetch_add(1, Ordering::Relaxed);
+        if took > period {
+            self.threadloop_overrun.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+// Builds a text in the Prometheus exposition format
+#[derive(Default)]
+pub struct MetricsWriter(String);
+
+impl MetricsWriter {
+    pub fn header(&mut self, name: &str, mtype: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {name} {help}");
+        let _ = writeln!(self.0, "# TYPE {name} {mtype}");
+    }
+
+    pub fn value(&mut self, name: &str, labels: &[(&str, &str)], val: f64) {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(k, v)| format!("{k}=\"{}\"", escape_label(v)))
+                .collect();
+            let _ = write!(self.0, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.0, " {val}");
+    }
+
+    pub fn metric(&mut self, name: &str, mtype: &str, help: &str, val: f64) {
+        self.header(name, mtype, help);
+        self.value(name, &[], val);
+    }
+
+    pub fn finish(self) -> String {
+        self.0
+    }
+}
+
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl Game {
+    // Must be called without holding any lock
+    pub async fn render_metrics(&self, out: &mut MetricsWriter) {
+        let mut nb_players = 0;
+        let mut nb_lost = 0;
+        let mut nb_stations = 0;
+        let mut money = 0.0;
+        let mut states: BTreeMap<&'static str, usize> =
+            ShipState::VARIANTS.iter().map(|s| (*s, 0)).collect();
+
+        let players = self.players.read().await;
+        for player in players.values() {
+            let player = player.read().await;
+            nb_players += 1;
+            if player.lost {
+                nb_lost += 1;
+                continue;
+            }
+            nb_stations += player.stations.len();
+            money += player.money;
+            for ship in player.ships.values() {
+                *states.entry((&ship.state).into()).or_default() += 1;
+            }
+        }
+        drop(players);
+
+        out.metric(
+            "simeis_tick",
+            "counter",
+            "Number of ticks simulated",
+            self.clock.tick() as f64,
+        );
+        out.metric(
+            "simeis_tick_period_seconds",
+            "gauge",
+            "Expected real time between two ticks",
+            self.clock.period().as_secs_f64(),
+        );
+        out.metric(
+            "simeis_inflation",
+            "gauge",
+            "Current inflation index applied on the prices",
+            self.inflation(),
+        );
+        let m = &self.metrics;
+        out.metric(
+            "simeis_threadloop_last_seconds",
+            "gauge",
+            "Duration of the last game loop",
+            m.threadloop_last_ns.load(Ordering::Relaxed) as f64 / 1e9,
+        );
+        out.header(
+            "simeis_threadloop_seconds",
+            "summary",
+            "Duration of the game loops",
+        );
+        out.value(
+            "simeis_threadloop_seconds_sum",
+            &[],
+            m.threadloop_sum_ns.load(Ordering::Relaxed) as f64 / 1e9,
+        );
+        out.value(
+            "simeis_threadloop_seconds_count",
+            &[],
+            m.threadloop_count.load(Ordering::Relaxed) as f64,
+        );
+        out.metric(
+            "simeis_threadloop_overrun_total",
+            "counter",
+            "Game loops that took longer than the tick period",
+            m.threadloop_overrun.load(Ordering::Relaxed) as f64,
+        );
+
+        out.metric(
+            "simeis_players",
+            "gauge",
+            "Number of players",
+            nb_players as f64,
+        );
+        out.metric(
+            "simeis_players_lost",
+            "gauge",
+            "Number of players who lost",
+            nb_lost as f64,
+        );
+        out.metric(
+            "simeis_stations",
+            "gauge",
+            "Number of stations owned by players still in game",
+            nb_stations as f64,
+        );
+        out.metric(
+            "simeis_money",
+            "gauge",
+            "Total money of the players still in game",
+            money,
+        );
+        out.header("simeis_ships", "gauge", "Number of ships per state");
+        for (state, nb) in states {
+            out.value("simeis_ships", &[("state", state)], nb as f64);
+        }
+
+        out.header(
+            "simeis_market_price",
+            "gauge",
+            "Current market price of a resource",
+        );
+        let market = self.market.read().await;
+        for (res, price) in market.prices.iter() {
+            out.value(
+                "simeis_market_price",
+                &[("resource", &format!("{res:?}"))],
+                *price,
+            );
+        }
+        drop(market);
+
+        out.header(
+            "simeis_syslog_events_total",
+            "counter",
+            "Syslog events produced per type",
+        );
+        let events = m.syslog_events.lock().unwrap().clone();
+        for (evt, nb) in events {
+            out.value("simeis_syslog_events_total", &[("type", evt)], nb as f64);
+        }
+    }
+}
+
+#[test]
+fn test_metrics_writer() {
+    let mut out = MetricsWriter::default();
+    out.metric("a_total", "counter", "Some help", 3.0);
+    out.value("b", &[("l", "x\"y"), ("m", "z")], 0.5);
+    assert_eq!(
+        out.finish(),
+        "# HELP a_total Some help\n# TYPE a_total counter\na_total 3\nb{l=\"x\\\"y\",m=\"z\"} 0.5\n"
+    );
+}
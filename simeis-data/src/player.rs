@@ -1,19 +1,31 @@
-use rand::RngCore;
+use rand::{Rng, RngCore};
 use std::collections::hash_map::DefaultHasher;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::hash::Hasher;
 use std::time::Instant;
 
-use crate::crew::CrewId;
+use crate::achievement::{Achievement, FLEET_ADMIRAL_SHIPS};
+use crate::crew::recruitment::hiring_cost;
+use crate::crew::{CrewId, CrewMember, CrewMemberType};
 use crate::errors::Errcode;
+use crate::galaxy::economy::EquipmentKind;
+use crate::galaxy::map::KnownMap;
+use crate::galaxy::scan::ScanResult;
 use crate::galaxy::station::{Station, StationId};
-use crate::galaxy::{Galaxy, SpaceCoord};
+use crate::galaxy::{compute_sector, Galaxy, SpaceCoord};
+use crate::score::{Score, ScoreHistory};
+use crate::ship::generation;
 use crate::ship::module::{ShipModuleId, ShipModuleType};
+use crate::ship::resources::Resource;
+use crate::ship::towing::TowInfo;
 use crate::ship::upgrade::ShipUpgrade;
-use crate::ship::{Ship, ShipId};
+use crate::ship::{Ship, ShipId, ShipState};
 use crate::syslog::{SyslogEvent, SyslogRecv};
 
 const INIT_MONEY: f64 = 72000.0;
+// Warn the player when there is less than a minute of wages left
+const LOW_FUNDS_SEC: f64 = 60.0;
+const FIRST_DISCOVERY_SCORE: f64 = 500.0;
 
 pub type PlayerId = u16;
 pub type PlayerKey = [u8; 128];
@@ -25,6 +37,11 @@ pub struct Player {
     pub key: PlayerKey,
     pub score: f64,
     pub lost: bool,
+    pub trade_profit: f64,
+    pub bonus: f64,
+    pub scores: ScoreHistory,
+    pub achievements: BTreeSet<Achievement>,
+    unlocked: Vec<Achievement>,
 
     pub name: String,
     pub money: f64,
@@ -32,12 +49,17 @@ pub struct Player {
 
     pub stations: BTreeMap<StationId, SpaceCoord>,
     pub ships: BTreeMap<ShipId, Ship>,
+    pub map: KnownMap,
 }
 
 impl Player {
-    pub fn new(station: (StationId, SpaceCoord), name: String) -> Player {
+    pub fn compute_id(name: &str) -> PlayerId {
         let mut hasher = DefaultHasher::new();
         hasher.write(name.as_bytes());
+        (hasher.finish() % (PlayerId::MAX as u64)) as PlayerId
+    }
+
+    pub fn new(id: PlayerId, station: (StationId, SpaceCoord), name: String) -> Player {
         let mut rng = rand::rng();
         let mut randbytes = [0; 128];
         rng.fill_bytes(&mut randbytes);
@@ -51,22 +73,76 @@ impl Player {
         }
         let mut stations = BTreeMap::new();
         stations.insert(station.0, station.1);
+        let mut map = KnownMap::default();
+        let (x, y, z) = station.1;
+        map.sectors.insert(compute_sector(x, y, z));
+        let mut scores = ScoreHistory::default();
+        scores.push(Score::initial(money));
         Player {
             created: Instant::now(),
             key: randbytes,
-            id: (hasher.finish() % (PlayerId::MAX as u64)) as PlayerId,
+            id,
             lost: false,
 
             money,
-            score: 0.0,
+            score: money,
             costs: 0.0,
+            trade_profit: 0.0,
+            bonus: 0.0,
+            scores,
+            achievements: BTreeSet::new(),
+            unlocked: vec![],
 
             name,
             stations,
             ships: BTreeMap::new(),
+            map,
+        }
+    }
+
+    // The private view is only for the player itself
+    pub fn to_json(&self, private: bool) -> serde_json::Value {
+        if private {
+            serde_json::json!({
+                "id": self.id,
+                "name": self.name,
+                "stations": self.stations,
+                "money": self.money,
+                "ships": self.ships.values().collect::<Vec<&Ship>>(),
+                "costs": self.costs,
+                "score": self.score,
+                "lost": self.lost,
+                "achievements": self.achievements,
+            })
+        } else {
+            serde_json::json!({
+                "id": self.id,
+                "name": self.name,
+                "stations": self.stations,
+            })
+        }
+    }
+
+    // Being the first to discover a sector is rewarded
+    pub fn learn(&mut self, results: &ScanResult) {
+        self.map.learn(results);
+        self.bonus += FIRST_DISCOVERY_SCORE * (results.first_discovered as f64);
+        if results.first_discovered > 0 {
+            self.unlock(Achievement::FirstDiscovery);
+        }
+    }
+
+    // Newly unlocked achievements are announced and rewarded by the game thread
+    pub fn unlock(&mut self, achievement: Achievement) {
+        if self.achievements.insert(achievement) {
+            self.unlocked.push(achievement);
         }
     }
 
+    pub(crate) fn take_unlocked(&mut self) -> Vec<Achievement> {
+        std::mem::take(&mut self.unlocked)
+    }
+
     // SAFETY Will deadlock if a &mut station exists when this is called
     pub async fn update_wages(&mut self, galaxy: &Galaxy) {
         self.costs = 0.0;
@@ -88,12 +164,19 @@ impl Player {
             .sum::<f64>();
     }
 
-    pub async fn update_money(&mut self, syslog: &SyslogRecv, tdelta: f64) {
-        let before = self.money < (self.costs * 60.0);
-        self.money -= self.costs * tdelta;
-        let after = self.money < (self.costs * 60.0);
+    // Not enough money left to pay the wages for long
+    pub fn low_funds(&self, inflation: f64) -> bool {
+        self.money < (self.costs * inflation * LOW_FUNDS_SEC)
+    }
+
+    // The wages follow the inflation
+    pub async fn update_money(&mut self, syslog: &SyslogRecv, tdelta: f64, inflation: f64) {
+        let costs = self.costs * inflation;
+        let before = self.money < (costs * LOW_FUNDS_SEC);
+        self.money -= costs * tdelta;
+        let after = self.money < (costs * LOW_FUNDS_SEC);
         if after && !before {
-            let tleft = std::time::Duration::from_secs_f64(self.money / self.costs);
+            let tleft = std::time::Duration::from_secs_f64(self.money / costs);
             syslog.event(self.id, SyslogEvent::LowFunds(tleft)).await;
         }
         if self.money < 0.0 && !self.lost {
@@ -104,12 +187,17 @@ impl Player {
         }
     }
 
-    pub fn buy_ship(&mut self, station: &mut Station, id: ShipId) -> Result<ShipId, Errcode> {
+    pub fn buy_ship(
+        &mut self,
+        station: &mut Station,
+        id: ShipId,
+        inflation: f64,
+    ) -> Result<ShipId, Errcode> {
         let ship_opt = {
             let mut data = None;
             for (n, ship) in station.shipyard.iter().enumerate() {
                 if ship.id == id {
-                    data = Some((n, ship.compute_price()));
+                    data = Some((n, station.ship_price(ship, inflation)));
                 }
             }
             data
@@ -128,39 +216,376 @@ impl Player {
         ship.update_perf_stats();
         ship.fuel_tank = ship.fuel_tank_capacity;
         self.money -= price;
+        station.economy.record_purchase(EquipmentKind::Ship, price);
         self.ships.insert(id, ship);
+        self.unlock(Achievement::FirstShip);
+        if self.ships.len() >= FLEET_ADMIRAL_SHIPS {
+            self.unlock(Achievement::FleetAdmiral);
+        }
 
-        let pos = station.position;
-        station.shipyard.push(Ship::random(pos));
+        let offer =
+            generation::random_offer(&mut rand::rng(), station.position, station.offers_quality());
+        station.shipyard.push(offer);
         Ok(ship_id)
     }
 
+    // Removes a ship docked on the station from the fleet, its crew goes idle on the station
+    fn take_docked_ship(&mut self, station: &mut Station, id: &ShipId) -> Result<Ship, Errcode> {
+        let Some(ship) = self.ships.get(id) else {
+            return Err(Errcode::ShipNotFound(*id));
+        };
+        if ship.position != station.position {
+            return Err(Errcode::ShipNotInStation);
+        }
+        if !matches!(ship.state, ShipState::Idle) {
+            return Err(Errcode::ShipNotIdle);
+        }
+
+        let mut ship = self.ships.remove(id).unwrap();
+        station.idle_crew.0.extend(ship.unload_crew().0);
+        Ok(ship)
+    }
+
+    // The player wages must be updated after this call
+    pub fn sell_ship(
+        &mut self,
+        station: &mut Station,
+        id: &ShipId,
+        inflation: f64,
+    ) -> Result<f64, Errcode> {
+        if let Some(ship) = self.ships.get(id) {
+            if ship.cargo.usage > 0.0 {
+                return Err(Errcode::ShipCargoNotEmpty);
+            }
+        }
+        let ship = self.take_docked_ship(station, id)?;
+        let price = station.ship_resale_price(&ship, inflation);
+        self.money += price;
+        station.shipyard.push(ship);
+        Ok(price)
+    }
+
+    // The resources that don't fit in the station cargo are lost
+    // The player wages must be updated after this call
+    pub fn scrap_ship(
+        &mut self,
+        station: &mut Station,
+        id: &ShipId,
+    ) -> Result<BTreeMap<Resource, f64>, Errcode> {
+        let ship = self.take_docked_ship(station, id)?;
+        let mut recovered = BTreeMap::new();
+        for (res, amnt) in ship.scrap_resources() {
+            let added = station.cargo.add_resource(&res, amnt);
+            if added > 0.0 {
+                recovered.insert(res, added);
+            }
+        }
+        Ok(recovered)
+    }
+
+    // Moves a crew member between two ships docked on the station,
+    // returns the module it operates, if it's not a pilot
+    pub fn transfer_crew(
+        &mut self,
+        station: &Station,
+        crew_id: CrewId,
+        from: &ShipId,
+        to: &ShipId,
+    ) -> Result<Option<ShipModuleId>, Errcode> {
+        if from == to {
+            return Err(Errcode::InvalidArgument("ship_id"));
+        }
+        for id in [from, to] {
+            let Some(ship) = self.ships.get(id) else {
+                return Err(Errcode::ShipNotFound(*id));
+            };
+            if ship.position != station.position {
+                return Err(Errcode::ShipNotInStation);
+            }
+            if !matches!(ship.state, ShipState::Idle) {
+                return Err(Errcode::ShipNotIdle);
+            }
+        }
+        let Some(cm) = self.ships[from].crew.0.get(&crew_id) else {
+            return Err(Errcode::CrewMemberNotFound(crew_id));
+        };
+
+        let dest = &self.ships[to];
+        let post = match cm.member_type {
+            CrewMemberType::Pilot if dest.pilot.is_none() => None,
+            CrewMemberType::Operator => {
+                let Some((modid, _)) = dest.modules.iter().find(|(_, m)| m.need(&cm.member_type))
+                else {
+                    return Err(Errcode::CrewNotNeeded);
+                };
+                Some(*modid)
+            }
+            _ => return Err(Errcode::CrewNotNeeded),
+        };
+
+        let cm = self
+            .ships
+            .get_mut(from)
+            .unwrap()
+            .remove_crew_member(&crew_id)
+            .unwrap();
+        let dest = self.ships.get_mut(to).unwrap();
+        dest.crew.0.insert(crew_id, cm);
+        match post {
+            None => dest.pilot = Some(crew_id),
+            Some(modid) => dest.modules.get_mut(&modid).unwrap().operator = Some(crew_id),
+        }
+        dest.update_perf_stats();
+        Ok(post)
+    }
+
+    // Two ships can exchange fuel and resources when they are at the same place,
+    // even in open space, as long as none of them is flying
+    fn check_rendezvous(&self, from: &ShipId, to: &ShipId) -> Result<(), Errcode> {
+        if from == to {
+            return Err(Errcode::InvalidArgument("ship_id"));
+        }
+        for id in [from, to] {
+            let Some(ship) = self.ships.get(id) else {
+                return Err(Errcode::ShipNotFound(*id));
+            };
+            if matches!(ship.state, ShipState::InFlight(_) | ShipState::Towed(_)) {
+                return Err(Errcode::ShipNotIdle);
+            }
+        }
+        if self.ships[from].position != self.ships[to].position {
+            return Err(Errcode::ShipsNotTogether);
+        }
+        Ok(())
+    }
+
+    // Moves fuel from the tank of a ship to the tank of another
+    pub fn transfer_fuel(&mut self, from: &ShipId, to: &ShipId, amnt: f64) -> Result<f64, Errcode> {
+        if !amnt.is_finite() || amnt <= 0.0 {
+            return Err(Errcode::InvalidArgument("amount"));
+        }
+        self.check_rendezvous(from, to)?;
+        let dest = &self.ships[to];
+        let amnt = amnt
+            .min(self.ships[from].fuel_tank)
+            .min(dest.fuel_tank_capacity - dest.fuel_tank)
+            .max(0.0);
+        self.ships.get_mut(from).unwrap().fuel_tank -= amnt;
+        self.ships.get_mut(to).unwrap().fuel_tank += amnt;
+        Ok(amnt)
+    }
+
+    // Moves resources from the cargo of a ship to the cargo of another,
+    // what doesn't fit in the destination stays in the source cargo
+    pub fn transfer_cargo(
+        &mut self,
+        from: &ShipId,
+        to: &ShipId,
+        resource: &Resource,
+        amnt: f64,
+    ) -> Result<f64, Errcode> {
+        if !amnt.is_finite() || amnt <= 0.0 {
+            return Err(Errcode::InvalidArgument("amount"));
+        }
+        self.check_rendezvous(from, to)?;
+        let unloaded = self
+            .ships
+            .get_mut(from)
+            .unwrap()
+            .cargo
+            .unload(resource, amnt);
+        if unloaded == 0.0 {
+            return Ok(0.0);
+        }
+        let added = self
+            .ships
+            .get_mut(to)
+            .unwrap()
+            .cargo
+            .add_resource(resource, unloaded);
+        if added < unloaded {
+            self.ships
+                .get_mut(from)
+                .unwrap()
+                .cargo
+                .add_resource(resource, unloaded - added);
+        }
+        Ok(added)
+    }
+
+    pub fn tow_offer(&self, ship_id: &ShipId, inflation: f64) -> Result<TowInfo, Errcode> {
+        let Some(ship) = self.ships.get(ship_id) else {
+            return Err(Errcode::ShipNotFound(*ship_id));
+        };
+        if self.stations.values().any(|coord| *coord == ship.position) {
+            return Err(Errcode::NullDistance);
+        }
+        let tow = TowInfo::nearest_station(&ship.position, &self.stations, inflation)
+            .ok_or(Errcode::NullDistance)?;
+        // Only a ship unable to fly back to the station by itself can be towed
+        if ship
+            .compute_travel_costs(tow.destination)
+            .is_ok_and(|cost| cost.have_enough(ship))
+        {
+            return Err(Errcode::ShipNotStranded);
+        }
+        Ok(tow)
+    }
+
+    // A stranded ship can be brought back to the nearest station for a price
+    pub fn call_tow(&mut self, ship_id: &ShipId, inflation: f64) -> Result<TowInfo, Errcode> {
+        let Some(ship) = self.ships.get(ship_id) else {
+            return Err(Errcode::ShipNotFound(*ship_id));
+        };
+        if !matches!(ship.state, ShipState::Idle) {
+            return Err(Errcode::ShipNotIdle);
+        }
+        let tow = self.tow_offer(ship_id, inflation)?;
+        let ship = self.ships.get_mut(ship_id).unwrap();
+        if self.money < tow.price {
+            return Err(Errcode::NotEnoughMoney(self.money, tow.price));
+        }
+        self.money -= tow.price;
+        ship.state = ShipState::Towed(tow.clone());
+        Ok(tow)
+    }
+
+    // The new crew member joins the idle crew of the station
+    // The player wages must be updated after this call
+    pub fn hire_crew(
+        &mut self,
+        station: &mut Station,
+        crewtype: CrewMemberType,
+        inflation: f64,
+    ) -> Result<(CrewId, f64), Errcode> {
+        let member = CrewMember::from(crewtype);
+        let cost = hiring_cost(&member, inflation);
+        if cost > self.money {
+            return Err(Errcode::NotEnoughMoney(self.money, cost));
+        }
+        self.money -= cost;
+        let id = self.new_crew_id(station);
+        station.idle_crew.0.insert(id, member);
+        Ok((id, cost))
+    }
+
+    // An ID not used by the crew of the station, nor aboard the ships of the player
+    fn new_crew_id(&self, station: &Station) -> CrewId {
+        let mut rng = rand::rng();
+        loop {
+            let id = rng.random();
+            let taken = station.idle_crew.0.contains_key(&id)
+                || station.crew.0.contains_key(&id)
+                || self
+                    .ships
+                    .values()
+                    .any(|ship| ship.crew.0.contains_key(&id));
+            if !taken {
+                return id;
+            }
+        }
+    }
+
+    // The player wages must be updated after this call
+    pub fn hire_candidate(
+        &mut self,
+        station: &mut Station,
+        id: CrewId,
+        inflation: f64,
+    ) -> Result<(CrewId, f64), Errcode> {
+        let Some(index) = station.candidates.iter().position(|c| c.id == id) else {
+            return Err(Errcode::CrewMemberNotFound(id));
+        };
+        let cost = hiring_cost(&station.candidates[index].member, inflation);
+        if cost > self.money {
+            return Err(Errcode::NotEnoughMoney(self.money, cost));
+        }
+        self.money -= cost;
+        let candidate = station.candidates.remove(index);
+        // The ID of the candidate could already be used by a crew member of the player
+        let crew_id = self.new_crew_id(station);
+        station.idle_crew.0.insert(crew_id, candidate.member);
+        Ok((crew_id, cost))
+    }
+
+    // Dismisses an idle crew member of the station, paying a severance
+    // The player wages must be updated after this call
+    pub fn fire_crew(
+        &mut self,
+        station: &mut Station,
+        crew_id: CrewId,
+        inflation: f64,
+    ) -> Result<f64, Errcode> {
+        let Some(cm) = station.idle_crew.0.get(&crew_id) else {
+            if station.crew.0.contains_key(&crew_id) {
+                return Err(Errcode::CrewMemberNotIdle(crew_id));
+            }
+            return Err(Errcode::CrewMemberNotFound(crew_id));
+        };
+        let severance = cm.severance(inflation);
+        if severance > self.money {
+            return Err(Errcode::NotEnoughMoney(self.money, severance));
+        }
+        self.money -= severance;
+        station.idle_crew.0.remove(&crew_id);
+        Ok(severance)
+    }
+
     pub fn buy_ship_module(
         &mut self,
-        station_id: &StationId,
+        station: &mut Station,
         ship_id: &ShipId,
         modtype: ShipModuleType,
+        inflation: f64,
     ) -> Result<ShipModuleId, Errcode> {
-        let Some(station) = self.stations.get(station_id) else {
-            return Err(Errcode::NoSuchStation(*station_id));
-        };
-
         let Some(ship) = self.ships.get_mut(ship_id) else {
             return Err(Errcode::ShipNotFound(*ship_id));
         };
 
-        if station != &ship.position {
+        if station.position != ship.position {
             return Err(Errcode::ShipNotInStation);
         }
 
-        let price = modtype.get_price_buy();
+        let free = ship.free_slots();
+        if modtype.slot_size() > free {
+            return Err(Errcode::NoModuleSlot(free));
+        }
+        let price = station.module_price(&modtype, inflation);
         if self.money < price {
             return Err(Errcode::NotEnoughMoney(self.money, price));
         }
         self.money -= price;
-        let id = (ship.modules.len() + 1) as ShipModuleId;
-        ship.modules.insert(id, modtype.new_module());
-        Ok(id)
+        station
+            .economy
+            .record_purchase(EquipmentKind::Module, price);
+        ship.add_module(modtype.new_module())
+    }
+
+    // The operator of the module goes back to the idle crew of the station
+    pub fn sell_ship_module(
+        &mut self,
+        station: &mut Station,
+        ship_id: &ShipId,
+        mod_id: &ShipModuleId,
+        inflation: f64,
+    ) -> Result<f64, Errcode> {
+        let Some(ship) = self.ships.get_mut(ship_id) else {
+            return Err(Errcode::ShipNotFound(*ship_id));
+        };
+        if station.position != ship.position {
+            return Err(Errcode::ShipNotInStation);
+        }
+        if !matches!(ship.state, ShipState::Idle) {
+            return Err(Errcode::ShipNotIdle);
+        }
+
+        let (module, operator) = ship.remove_module(mod_id)?;
+        if let Some((id, cm)) = operator {
+            station.idle_crew.0.insert(id, cm);
+        }
+        let price = station.module_resale_price(&module, inflation);
+        self.money += price;
+        Ok(price)
     }
 
     pub fn buy_ship_upgrade(
@@ -168,26 +593,31 @@ impl Player {
         station: &mut Station,
         ship_id: &ShipId,
         upgrade: &ShipUpgrade,
+        inflation: f64,
     ) -> Result<f64, Errcode> {
         let Some(ship) = self.ships.get_mut(ship_id) else {
             return Err(Errcode::ShipNotFound(*ship_id));
         };
 
-        let price = station.get_ship_upgrade_price(upgrade);
+        let price = station.get_ship_upgrade_price(upgrade, inflation);
         if price > self.money {
             return Err(Errcode::NotEnoughMoney(self.money, price));
         }
 
         self.money -= price;
+        station
+            .economy
+            .record_purchase(EquipmentKind::Upgrade, price);
         upgrade.install(ship);
         Ok(price)
     }
 
     pub fn buy_ship_module_upgrade(
         &mut self,
-        station: &Station,
+        station: &mut Station,
         ship_id: &ShipId,
         mod_id: &ShipModuleId,
+        inflation: f64,
     ) -> Result<(f64, u8), Errcode> {
         let Some(ship) = self.ships.get_mut(ship_id) else {
             return Err(Errcode::ShipNotFound(*ship_id));
@@ -198,15 +628,21 @@ impl Player {
         let Some(ref mut module) = ship.modules.get_mut(mod_id) else {
             return Err(Errcode::NoSuchModule(*mod_id));
         };
-        let price = module.price_next_rank();
+        if module.rank >= module.modtype.max_rank() {
+            return Err(Errcode::MaxRankReached(module.modtype.max_rank()));
+        }
+        let price = station.module_upgrade_price(module, inflation);
         if price > self.money {
             return Err(Errcode::NotEnoughMoney(self.money, price));
         }
 
         self.money -= price;
-        module.rank += 1;
-
-        Ok((price, module.rank))
+        station
+            .economy
+            .record_purchase(EquipmentKind::Module, price);
+        let rank = module.rank_up()?;
+        ship.update_perf_stats();
+        Ok((price, rank))
     }
 
     pub fn upgrade_crew_rank(
@@ -214,6 +650,7 @@ impl Player {
         station: &Station,
         ship_id: &ShipId,
         crew_id: &CrewId,
+        inflation: f64,
     ) -> Result<(f64, u8), Errcode> {
         let Some(ship) = self.ships.get_mut(ship_id) else {
             return Err(Errcode::ShipNotFound(*ship_id));
@@ -226,30 +663,258 @@ impl Player {
                 return Err(Errcode::CrewMemberNotFound(*crew_id));
             };
 
-            let price = cm.price_next_rank();
+            if cm.rank >= cm.member_type.max_rank() {
+                return Err(Errcode::MaxRankReached(cm.member_type.max_rank()));
+            }
+            let price = cm.price_next_rank(inflation);
             if price > self.money {
                 return Err(Errcode::NotEnoughMoney(self.money, price));
             }
 
             self.money -= price;
-            cm.rank += 1;
-            (price, cm.rank)
+            (price, cm.rank_up()?)
         };
         ship.update_perf_stats();
+        self.unlock(Achievement::FirstCrewUpgrade);
         Ok(res)
     }
 
-    pub fn upgrade_station_trader(&mut self, station: &mut Station) -> Result<(f64, u8), Errcode> {
+    pub fn upgrade_station_trader(
+        &mut self,
+        station: &mut Station,
+        inflation: f64,
+    ) -> Result<(f64, u8), Errcode> {
         let Some(trader_id) = station.trader else {
             return Err(Errcode::NoTraderAssigned);
         };
         let cm = station.crew.0.get_mut(&trader_id).unwrap();
-        let price = cm.price_next_rank();
+        if cm.rank >= cm.member_type.max_rank() {
+            return Err(Errcode::MaxRankReached(cm.member_type.max_rank()));
+        }
+        let price = cm.price_next_rank(inflation);
         if price > self.money {
             return Err(Errcode::NotEnoughMoney(self.money, price));
         }
         self.money -= price;
-        cm.rank += 1;
-        Ok((price, cm.rank))
+        let rank = cm.rank_up()?;
+        self.unlock(Achievement::FirstCrewUpgrade);
+        Ok((price, rank))
+    }
+}
+
+#[test]
+fn test_sell_and_scrap_ship() {
+    use crate::tests::{board_crew, fixed_ship, player_with_station};
+
+    let (mut player, mut station) = player_with_station("seller");
+    for id in [1, 2] {
+        let mut ship = fixed_ship(id, station.position);
+        board_crew(&mut ship, id as u32, CrewMemberType::Pilot);
+        player.ships.insert(id, ship);
+    }
+
+    let money = player.money;
+    let nb_offers = station.shipyard.len();
+    let price = player.sell_ship(&mut station, &1, 1.0).unwrap();
+    assert!(price > 0.0);
+    assert_eq!(player.money, money + price);
+    assert_eq!(station.shipyard.len(), nb_offers + 1);
+    assert!(station.shipyard.last().unwrap().crew.0.is_empty());
+
+    let recovered = player.scrap_ship(&mut station, &2).unwrap();
+    assert!(recovered.contains_key(&Resource::Iron));
+    assert!(player.ships.is_empty());
+    assert_eq!(station.idle_crew.0.len(), 2);
+    assert!(matches!(
+        player.scrap_ship(&mut station, &2),
+        Err(Errcode::ShipNotFound(2))
+    ));
+}
+
+#[test]
+fn test_transfer_and_fire_crew() {
+    use crate::ship::module::ShipModuleType;
+    use crate::tests::{board_crew, fixed_ship, player_with_station};
+
+    let (mut player, mut station) = player_with_station("manager");
+    for id in [1, 2] {
+        player.ships.insert(id, fixed_ship(id, station.position));
+    }
+    let ship = player.ships.get_mut(&1).unwrap();
+    board_crew(ship, 10, CrewMemberType::Pilot);
+    board_crew(ship, 11, CrewMemberType::Operator);
+    ship.modules.insert(1, ShipModuleType::Miner.new_module());
+    ship.modules.get_mut(&1).unwrap().operator = Some(11);
+    ship.update_perf_stats();
+
+    assert_eq!(player.transfer_crew(&station, 10, &1, &2).unwrap(), None);
+    assert!(player.ships[&1].pilot.is_none());
+    assert_eq!(player.ships[&1].stats.speed, 0.0);
+    assert_eq!(player.ships[&2].pilot, Some(10));
+    assert!(player.ships[&2].stats.speed > 0.0);
+    assert!(matches!(
+        player.transfer_crew(&station, 11, &1, &2),
+        Err(Errcode::CrewNotNeeded)
+    ));
+
+    let ship = player.ships.get_mut(&1).unwrap();
+    station.unboard_crew(11, ship).unwrap();
+    assert!(ship.modules[&1].operator.is_none());
+    let money = player.money;
+    let severance = player.fire_crew(&mut station, 11, 1.0).unwrap();
+    assert!(severance > 0.0);
+    assert_eq!(player.money, money - severance);
+    assert!(station.idle_crew.0.is_empty());
+}
+
+#[test]
+fn test_module_slots() {
+    use crate::ship::module::ShipModuleType;
+    use crate::tests::{board_crew, fixed_ship, player_with_station};
+
+    let (mut player, mut station) = player_with_station("tinkerer");
+    player.money = 1_000_000.0;
+    let mut ship = fixed_ship(1, station.position);
+    ship.module_slots = 3;
+    player.ships.insert(1, ship);
+
+    let miner = player
+        .buy_ship_module(&mut station, &1, ShipModuleType::Miner, 1.0)
+        .unwrap();
+    let scanner = player
+        .buy_ship_module(&mut station, &1, ShipModuleType::Scanner, 1.0)
+        .unwrap();
+    assert_ne!(miner, scanner);
+    let money = player.money;
+    assert!(matches!(
+        player.buy_ship_module(&mut station, &1, ShipModuleType::CargoPod, 1.0),
+        Err(Errcode::NoModuleSlot(0))
+    ));
+    assert_eq!(player.money, money);
+
+    let ship = player.ships.get_mut(&1).unwrap();
+    board_crew(ship, 10, CrewMemberType::Operator);
+    ship.modules.get_mut(&miner).unwrap().operator = Some(10);
+    let price = player
+        .sell_ship_module(&mut station, &1, &miner, 1.0)
+        .unwrap();
+    assert!(price > 0.0);
+    assert_eq!(player.money, money + price);
+    assert!(station.idle_crew.0.contains_key(&10));
+    assert_eq!(player.ships[&1].free_slots(), 2);
+
+    // Module IDs are not reused after a removal
+    let pod = player
+        .buy_ship_module(&mut station, &1, ShipModuleType::CargoPod, 1.0)
+        .unwrap();
+    assert!(pod != miner && pod != scanner);
+}
+
+#[test]
+fn test_transfer_between_ships() {
+    use crate::tests::{fixed_ship, player_with_station};
+
+    let (mut player, _) = player_with_station("tanker");
+    for id in [1, 2] {
+        let mut ship = fixed_ship(id, (10, 20, 30));
+        ship.fuel_tank = ship.fuel_tank_capacity / 2.0;
+        player.ships.insert(id, ship);
+    }
+    let fuel = player.ships[&1].fuel_tank + player.ships[&2].fuel_tank;
+    let moved = player.transfer_fuel(&1, &2, f64::MAX).unwrap();
+    assert!(moved > 0.0);
+    let total = player.ships[&1].fuel_tank + player.ships[&2].fuel_tank;
+    assert!((total - fuel).abs() < 1e-6);
+    assert!(player.ships[&2].fuel_tank <= player.ships[&2].fuel_tank_capacity);
+    for amnt in [-500.0, 0.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            player.transfer_fuel(&2, &1, amnt),
+            Err(Errcode::InvalidArgument("amount"))
+        ));
+    }
+    let after = player.ships[&1].fuel_tank + player.ships[&2].fuel_tank;
+    assert_eq!(after, total);
+
+    let ship = player.ships.get_mut(&1).unwrap();
+    let space = ship.cargo.space_for(&Resource::HullPlate);
+    ship.cargo.add_resource(&Resource::HullPlate, space);
+    let dest_space = player.ships[&2].cargo.space_for(&Resource::HullPlate);
+    let moved = player
+        .transfer_cargo(&1, &2, &Resource::HullPlate, space)
+        .unwrap();
+    assert!((moved - space.min(dest_space)).abs() < 1e-6);
+    let left = player.ships[&1].cargo.resources[&Resource::HullPlate];
+    assert!((left + moved - space).abs() < 1e-6);
+
+    for amnt in [-500.0, 0.0, f64::NAN] {
+        assert!(matches!(
+            player.transfer_cargo(&2, &1, &Resource::HullPlate, amnt),
+            Err(Errcode::InvalidArgument("amount"))
+        ));
     }
+    assert!((player.ships[&1].cargo.resources[&Resource::HullPlate] - left).abs() < 1e-6);
+
+    player.ships.get_mut(&2).unwrap().position = (0, 0, 0);
+    assert!(matches!(
+        player.transfer_fuel(&1, &2, 1.0),
+        Err(Errcode::ShipsNotTogether)
+    ));
+}
+
+#[test]
+fn test_tow_service() {
+    use crate::tests::{board_crew, fixed_ship, player_with_station};
+
+    let (mut player, _) = player_with_station("stranded");
+    player.stations.insert(8, (100, 100, 100));
+    let mut ship = fixed_ship(1, (90, 90, 90));
+    board_crew(&mut ship, 10, CrewMemberType::Pilot);
+    player.ships.insert(1, ship);
+
+    // A ship able to fly back by itself doesn't need to be towed
+    assert!(matches!(
+        player.call_tow(&1, 1.0),
+        Err(Errcode::ShipNotStranded)
+    ));
+
+    player.ships.get_mut(&1).unwrap().fuel_tank = 0.0;
+    let money = player.money;
+    let tow = player.call_tow(&1, 1.0).unwrap();
+    assert_eq!(tow.station, 8);
+    assert_eq!(player.money, money - tow.price);
+    assert!(matches!(
+        player.call_tow(&1, 1.0),
+        Err(Errcode::ShipNotIdle)
+    ));
+
+    let ship = player.ships.get_mut(&1).unwrap();
+    assert!(!ship.update_tow(tow.remaining / 2.0));
+    assert_eq!(ship.position, (90, 90, 90));
+    assert!(ship.update_tow(tow.remaining));
+    assert_eq!(ship.position, (100, 100, 100));
+    ship.state = ShipState::Idle;
+    assert!(matches!(
+        player.call_tow(&1, 1.0),
+        Err(Errcode::NullDistance)
+    ));
+}
+
+#[test]
+fn test_hire_candidate_id_collision() {
+    use crate::crew::recruitment::Candidate;
+    use crate::tests::player_with_station;
+
+    let (mut player, mut station) = player_with_station("recruiter");
+    let trader = CrewMember::from(CrewMemberType::Trader);
+    station.idle_crew.0.insert(5, trader);
+    station.candidates = vec![Candidate {
+        id: 5,
+        member: CrewMember::from(CrewMemberType::Pilot),
+    }];
+
+    let (id, _) = player.hire_candidate(&mut station, 5, 1.0).unwrap();
+    assert_ne!(id, 5);
+    assert_eq!(station.idle_crew.0.len(), 2);
+    assert_eq!(station.idle_crew.0[&5].member_type, CrewMemberType::Trader);
+    assert_eq!(station.idle_crew.0[&id].member_type, CrewMemberType::Pilot);
 }
@@ -0,0 +1,219 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::galaxy::station::SHIP_RESALE_RATE;
+use crate::galaxy::Galaxy;
+use crate::player::{Player, PlayerId};
+use crate::ship::cargo::ShipCargo;
+use crate::ship::resources::Resource;
+
+// Game seconds between two snapshots of the scores
+pub const SCORE_SNAPSHOT_SEC: f64 = 30.0;
+const SCORE_HISTORY_SIZE: usize = 120;
+
+#[derive(Serialize, Clone, Copy, Default, Debug)]
+pub struct Score {
+    pub tick: u64,
+    // Net worth, with the bonus points earned
+    pub total: f64,
+    pub net_worth: f64,
+    pub money: f64,
+    // Resale value of the ships, worn hull included, regardless of the station economies
+    pub fleet: f64,
+    // Value of the resources in the cargos, at the current market prices
+    pub cargo: f64,
+    pub trading: f64,
+    pub bonus: f64,
+    pub nb_ships: usize,
+}
+
+impl Score {
+    pub fn initial(money: f64) -> Score {
+        Score {
+            total: money,
+            net_worth: money,
+            money,
+            ..Default::default()
+        }
+    }
+
+    // SAFETY Will deadlock if a &mut station of this player exists when this is called
+    pub async fn compute(
+        tick: u64,
+        player: &Player,
+        galaxy: &Galaxy,
+        prices: &BTreeMap<Resource, f64>,
+        inflation: f64,
+    ) -> Score {
+        let mut cargo = 0.0;
+        for coord in player.stations.values() {
+            let Some(station) = galaxy.get_station(coord).await else {
+                continue;
+            };
+            cargo += cargo_value(&station.read().await.cargo, prices);
+        }
+
+        let mut fleet = 0.0;
+        for ship in player.ships.values() {
+            fleet += ship.compute_price(inflation) * ship.hull_value_factor() * SHIP_RESALE_RATE;
+            cargo += cargo_value(&ship.cargo, prices);
+        }
+
+        let net_worth = player.money + fleet + cargo;
+        Score {
+            tick,
+            total: net_worth + player.bonus,
+            net_worth,
+            money: player.money,
+            fleet,
+            cargo,
+            trading: player.trade_profit,
+            bonus: player.bonus,
+            nb_ships: player.ships.len(),
+        }
+    }
+}
+
+fn cargo_value(cargo: &ShipCargo, prices: &BTreeMap<Resource, f64>) -> f64 {
+    cargo
+        .resources
+        .iter()
+        .map(|(r, amnt)| prices.get(r).copied().unwrap_or(r.base_price()) * amnt)
+        .sum()
+}
+
+// Last snapshots of the score of a player, oldest first
+#[derive(Default, Debug)]
+pub struct ScoreHistory(VecDeque<Score>);
+
+impl ScoreHistory {
+    pub fn push(&mut self, score: Score) {
+        if self.0.len() >= SCORE_HISTORY_SIZE {
+            self.0.pop_front();
+        }
+        self.0.push_back(score);
+    }
+
+    pub fn last(&self) -> Option<&Score> {
+        self.0.back()
+    }
+
+    // Progression since the previous snapshot
+    pub fn delta(&self) -> f64 {
+        let n = self.0.len();
+        if n < 2 {
+            return 0.0;
+        }
+        self.0[n - 1].total - self.0[n - 2].total
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Score> {
+        self.0.iter()
+    }
+}
+
+pub struct LeaderboardEntry {
+    pub id: PlayerId,
+    pub name: String,
+    pub score: Score,
+    pub delta: f64,
+}
+
+impl LeaderboardEntry {
+    pub fn new(player: &Player) -> LeaderboardEntry {
+        LeaderboardEntry {
+            id: player.id,
+            name: player.name.clone(),
+            score: player.scores.last().copied().unwrap_or_default(),
+            delta: player.scores.delta(),
+        }
+    }
+}
+
+fn ranking<F>(entries: &mut [LeaderboardEntry], key: F) -> Vec<Value>
+where
+    F: Fn(&Score) -> f64,
+{
+    entries.sort_by(|a, b| key(&b.score).total_cmp(&key(&a.score)));
+    entries
+        .iter()
+        .enumerate()
+        .map(|(n, e)| {
+            json!({
+                "rank": n + 1,
+                "id": e.id,
+                "name": e.name,
+                "value": key(&e.score),
+            })
+        })
+        .collect()
+}
+
+pub fn leaderboard(mut entries: Vec<LeaderboardEntry>) -> Value {
+    let richest = ranking(&mut entries, |s| s.money);
+    let best_trader = ranking(&mut entries, |s| s.trading);
+    // Ties on the number of ships are broken by the value of the fleet
+    let biggest_fleet = ranking(&mut entries, |s| {
+        (s.nb_ships as f64) + (s.fleet / (1.0 + s.fleet))
+    });
+
+    entries.sort_by(|a, b| b.score.total.total_cmp(&a.score.total));
+    let global: Vec<Value> = entries
+        .iter()
+        .enumerate()
+        .map(|(n, e)| {
+            json!({
+                "rank": n + 1,
+                "id": e.id,
+                "name": e.name,
+                "score": e.score.total,
+                "delta": e.delta,
+                "details": e.score,
+            })
+        })
+        .collect();
+
+    json!({
+        "ranking": global,
+        "richest": richest,
+        "best-trader": best_trader,
+        "biggest-fleet": biggest_fleet,
+    })
+}
+
+#[test]
+fn test_leaderboard() {
+    let entry = |id: PlayerId, money: f64, trading: f64, nb_ships: usize| LeaderboardEntry {
+        id,
+        name: format!("p{id}"),
+        score: Score {
+            total: money + trading,
+            money,
+            trading,
+            nb_ships,
+            ..Default::default()
+        },
+        delta: 0.0,
+    };
+
+    let board = leaderboard(vec![
+        entry(1, 100.0, 50.0, 1),
+        entry(2, 300.0, -10.0, 0),
+        entry(3, 10.0, 400.0, 3),
+    ]);
+    let ids = |cat: &str| -> Vec<u64> {
+        board[cat]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["id"].as_u64().unwrap())
+            .collect()
+    };
+    assert_eq!(ids("ranking"), vec![3, 2, 1]);
+    assert_eq!(ids("richest"), vec![2, 1, 3]);
+    assert_eq!(ids("best-trader"), vec![3, 1, 2]);
+    assert_eq!(ids("biggest-fleet"), vec![3, 1, 2]);
+    assert_eq!(board["ranking"][0]["rank"], 1);
+}
@@ -1,27 +1,52 @@
 use std::collections::BTreeMap;
 
 use cargo::ShipCargo;
-use module::{ShipModule, ShipModuleId};
-use navigation::{FlightData, Travel, TravelCost};
-use rand::Rng;
+use generation::{HullClass, Rarity};
+use module::{ShipModule, ShipModuleId, ShipModuleType};
+use navigation::{FlightData, FlightStatus, Travel, TravelCost};
 use resources::{ExtractionInfo, Resource};
 use serde::{Deserialize, Serialize};
 use shipstats::ShipStats;
+use strum::{IntoStaticStr, VariantNames};
+use towing::TowInfo;
 
-use crate::crew::{Crew, CrewId, CrewMemberType};
+use crate::crew::{Crew, CrewId, CrewMember, CrewMemberType, CrewTrait, MORALE_DANGER_LOSS};
 use crate::errors::Errcode;
+use crate::galaxy::scan::ScanResult;
 use crate::galaxy::station::Station;
 use crate::galaxy::{translation, Galaxy, SpaceCoord};
+use crate::player::PlayerId;
 
 pub mod cargo;
+pub mod generation;
 pub mod module;
 pub mod navigation;
 pub mod resources;
 pub mod shipstats;
+pub mod towing;
 pub mod upgrade;
 
-const PILOT_FUEL_SHARE: u8 = 5; // Rank 10 = 4/5 fuel consumption
+// The pilot saves up to 40% of fuel, rank 10 = 27% saved
+const PILOT_FUEL_SAVING_MAX: f64 = 0.4;
+const PILOT_FUEL_RANK_SCALE: f64 = 8.0;
+// Rank 10 = 7x faster than rank 1, rank 20 = 12.7x
+const PILOT_SPEED_RANK_POWF: f64 = 0.85;
 const HULL_USAGE_BASE: f64 = 5.0 / 100.0;
+const HULL_WEAR_SPEED_MALUS: f64 = 0.5; // Hull worn at 70% = 35% slower
+const HULL_WEAR_EXTRACT_MALUS: f64 = 0.25;
+// Arriving with a worn hull or an almost empty tank frightens the crew
+const DANGER_HULL_WEAR: f64 = 0.75;
+const DANGER_FUEL_RATIO: f64 = 0.1;
+const HULL_WEAR_VALUE_MALUS: f64 = 0.5; // Hull worn at 50% = 25% cheaper
+
+// An alert is sent when the hull wear goes past one of these ratios
+pub const HULL_CRITICAL_THRESHOLDS: [f64; 3] = [0.5, 0.8, 0.95];
+
+// Resources recovered when scrapping a ship
+const SCRAP_HULL_PLATE_RATE: f64 = 0.5;
+const SCRAP_IRON_PER_POWER: f64 = 40.0;
+const SCRAP_IRON_PER_CARGO: f64 = 0.2;
+const SCRAP_COPPER_PER_MODULE_RANK: f64 = 15.0;
 
 const FUEL_TANK_CAP_PRICE: f64 = 30.0;
 const CARGO_CAP_PRICE: f64 = 20.0;
@@ -30,15 +55,30 @@ const REACTOR_POWER_PRICE: f64 = 4000.0;
 const SHIELD_PRICE: f64 = 2500.0;
 
 const REACTOR_SPEED_PER_POWER: f64 = 50.0;
+// Hull repaired each second by a rank 1 operator, using the hull plates of the cargo
+const SELF_REPAIR_RATE: f64 = 3.0;
+// Slots of the ships created before the hull classes defined them
+const DEFAULT_MODULE_SLOTS: u8 = 4;
+
+const SCAN_RANK_SHIPS: u8 = 3;
+const SCAN_RANK_SHIP_DESTINATION: u8 = 5;
 
 pub type ShipId = u64;
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default, IntoStaticStr, VariantNames)]
 pub enum ShipState {
     #[default]
     Idle,
     InFlight(FlightData),
     Extracting(ExtractionInfo),
+    Repairing(RepairInfo),
+    Towed(TowInfo),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct RepairInfo {
+    // Hull repaired per second
+    pub rate: f64,
 }
 
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
@@ -49,7 +89,16 @@ pub struct Ship {
     pub hull_decay_capacity: f64,
     pub modules: BTreeMap<ShipModuleId, ShipModule>,
     pub shield_power: u16,
+    #[serde(default = "default_module_slots")]
+    pub module_slots: u8,
+    // Module IDs are never reused on a ship
+    #[serde(default)]
+    pub next_module_id: ShipModuleId,
 
+    #[serde(default)]
+    pub hull_class: HullClass,
+    #[serde(default)]
+    pub rarity: Rarity,
     #[serde(default)]
     pub position: SpaceCoord,
     #[serde(default)]
@@ -64,102 +113,103 @@ pub struct Ship {
     pub pilot: Option<CrewId>,
     #[serde(default)]
     pub state: ShipState,
+    // Resources extracted since the start of the last extraction
+    #[serde(default)]
+    pub extracted: BTreeMap<Resource, f64>,
     #[serde(default)]
     pub stats: shipstats::ShipStats,
 }
 
-impl Ship {
-    pub fn init_shipyard(position: SpaceCoord) -> Vec<Ship> {
-        let mut rng = rand::rng();
-        vec![
-            Ship::light(rng.random(), position),
-            Ship::medium(rng.random(), position),
-            Ship::heavy(rng.random(), position),
-        ]
-    }
+// Informations that can be scanned from a ship in flight
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ShipInfo {
+    pub id: ShipId,
+    pub owner: PlayerId,
+    pub position: SpaceCoord,
+    pub destination: Option<SpaceCoord>,
+}
 
-    pub fn random(position: SpaceCoord) -> Ship {
-        let mut rng = rand::rng();
-        let cargo_cap = rng.random_range(10.0..1000.0) as f64;
-        Ship {
-            id: rng.random(),
-            position,
-            reactor_power: rng.random_range(1..10),
-            fuel_tank_capacity: rng.random_range(1..10000) as f64,
-            cargo: ShipCargo::with_capacity(cargo_cap),
-            hull_decay_capacity: rng.random_range(1000..50000) as f64,
-            ..Default::default()
+impl ShipInfo {
+    pub fn new(owner: PlayerId, ship: &Ship) -> ShipInfo {
+        let destination = match ship.state {
+            ShipState::InFlight(ref data) => Some(data.destination),
+            _ => None,
+        };
+        ShipInfo {
+            id: ship.id,
+            owner,
+            position: ship.position,
+            destination,
         }
     }
 
-    fn light(id: ShipId, position: SpaceCoord) -> Ship {
-        Ship {
-            id,
-            position,
-            reactor_power: 1,
-            fuel_tank_capacity: 1000.0,
-            cargo: ShipCargo::with_capacity(200.0),
-            hull_decay_capacity: 3000.0,
-            shield_power: 0,
-            ..Default::default()
+    // Returns None if the scanner is not powerful enough to detect ships
+    pub fn scan(&self, rank: u8) -> Option<ShipInfo> {
+        if rank < SCAN_RANK_SHIPS {
+            return None;
         }
-    }
-
-    fn medium(id: ShipId, position: SpaceCoord) -> Ship {
-        Ship {
-            id,
-            position,
-            reactor_power: 3,
-            fuel_tank_capacity: 2000.0,
-            cargo: ShipCargo::with_capacity(400.0),
-            hull_decay_capacity: 6000.0,
-            shield_power: 1,
-            ..Default::default()
+        let mut info = self.clone();
+        if rank < SCAN_RANK_SHIP_DESTINATION {
+            info.destination = None;
         }
+        Some(info)
     }
+}
 
-    fn heavy(id: ShipId, position: SpaceCoord) -> Ship {
-        Ship {
-            id,
-            position,
-            reactor_power: 10,
-            fuel_tank_capacity: 4000.0,
-            cargo: ShipCargo::with_capacity(1200.0),
-            hull_decay_capacity: 20000.0,
-            shield_power: 3,
-            ..Default::default()
-        }
-    }
+fn default_module_slots() -> u8 {
+    DEFAULT_MODULE_SLOTS
+}
 
-    // TO DO (#22) Create a new ship with random specs
-    //         Used by traders to seek nice ships to buy
+// Multiplier on the fuel consumption, from 1.0 at rank 1 down to the max saving
+#[inline]
+fn pilot_fuel_factor(rank: u8) -> f64 {
+    let rank = (rank.max(1) - 1) as f64;
+    1.0 - PILOT_FUEL_SAVING_MAX * (1.0 - (-rank / PILOT_FUEL_RANK_SCALE).exp())
+}
+
+#[inline]
+fn pilot_speed_factor(rank: u8) -> f64 {
+    (rank as f64).powf(PILOT_SPEED_RANK_POWF)
+}
+
+impl Ship {
+    // Ship with random specs, of any class and rarity
+    pub fn random(position: SpaceCoord) -> Ship {
+        generation::random_offer(&mut rand::rng(), position, 1.0)
+    }
 
     // Public data of this ship to display on the marketplace
-    pub fn market_data(&self) -> serde_json::Value {
+    pub fn market_data(&self, inflation: f64) -> serde_json::Value {
         serde_json::json!({
             "id": self.id,
-            "price": self.compute_price(),
+            "price": self.compute_price(inflation),
+            "hull_class": self.hull_class,
+            "rarity": self.rarity,
             "modules": self.modules,
             "reactor_power": self.reactor_power,
             "cargo_capacity": self.cargo.capacity,
             "fuel_tank_capacity": self.fuel_tank_capacity,
             "hull_decay_capacity": self.hull_decay_capacity,
+            "shield_power": self.shield_power,
         })
     }
 
-    pub fn compute_price(&self) -> f64 {
+    pub fn compute_price(&self, inflation: f64) -> f64 {
         let mut price = 0.0;
         price += (self.reactor_power as f64) * REACTOR_POWER_PRICE;
-        price += self.fuel_tank_capacity * FUEL_TANK_CAP_PRICE;
-        price += self.cargo.capacity * CARGO_CAP_PRICE;
+        // The capacities given by the modules are paid with the modules
+        price += (self.fuel_tank_capacity - self.stats.fuel_tank_bonus) * FUEL_TANK_CAP_PRICE;
+        price += (self.cargo.capacity - self.stats.cargo_bonus) * CARGO_CAP_PRICE;
         price += self.hull_decay_capacity * HULL_DECAY_CAP_PRICE;
-        price += self.modules.values().map(|m| m.totalcost).sum::<f64>();
-        price
+        price += (self.shield_power as f64) * SHIELD_PRICE;
+        price += self.modules.values().map(|m| m.value(1.0)).sum::<f64>();
+        price * inflation
     }
 
     // Updates the performances of the ship based on the crew onboard
     pub fn update_perf_stats(&mut self) {
-        self.stats = ShipStats::default();
+        let old = std::mem::take(&mut self.stats);
+        self.update_modules_capacity(&old);
         self.stats.hull_usage_rate =
             HULL_USAGE_BASE / (1.0 + (1.0 + self.shield_power as f64).log(3.5));
         self.stats.fuel_consumption = self.reactor_power as f64;
@@ -167,15 +217,158 @@ impl Ship {
         if let Some(ref pilot) = self.pilot {
             let pilot = self.crew.0.get(pilot).unwrap();
             debug_assert!(matches!(pilot.member_type, CrewMemberType::Pilot));
-            // TO DO Handle case where pilot rank > PILOT_FUEL_SHARE * 10
-            let totshare = (PILOT_FUEL_SHARE * 10) as f64;
-            self.stats.fuel_consumption *= (totshare - (pilot.rank as f64)) / totshare;
-            self.stats.speed =
-                (self.reactor_power as f64) * REACTOR_SPEED_PER_POWER * (pilot.rank as f64);
+            self.stats.fuel_consumption *= pilot_fuel_factor(pilot.rank);
+            self.stats.fuel_consumption *= pilot.trait_factor(CrewTrait::fuel_factor);
+            self.stats.fuel_consumption /= pilot.performance();
+            self.stats.speed = (self.reactor_power as f64)
+                * REACTOR_SPEED_PER_POWER
+                * pilot_speed_factor(pilot.rank);
+            self.stats.speed *= pilot.performance() * pilot.trait_factor(CrewTrait::speed_factor);
         } else {
             self.stats.speed = 0.0;
         };
+        for module in self.modules.values() {
+            let (speed, fuel) = module.afterburner();
+            self.stats.speed *= speed;
+            self.stats.fuel_consumption *= fuel;
+            self.stats.repair_rate += module.repair_rate();
+        }
         self.stats.speed *= 1.0 - self.cargo.slowing_ratio();
+        self.stats.speed *= self.hull_speed_factor();
+    }
+
+    pub fn free_slots(&self) -> u8 {
+        let used: u8 = self.modules.values().map(|m| m.modtype.slot_size()).sum();
+        self.module_slots.saturating_sub(used)
+    }
+
+    pub fn add_module(&mut self, module: ShipModule) -> Result<ShipModuleId, Errcode> {
+        let free = self.free_slots();
+        if module.modtype.slot_size() > free {
+            return Err(Errcode::NoModuleSlot(free));
+        }
+        let last = self.modules.keys().next_back().copied().unwrap_or(0);
+        let Some(id) = last.checked_add(1).map(|id| self.next_module_id.max(id)) else {
+            return Err(Errcode::NoModuleIdLeft);
+        };
+        // The IDs are never reused, so the next one must exist as well
+        let Some(next) = id.checked_add(1) else {
+            return Err(Errcode::NoModuleIdLeft);
+        };
+        self.next_module_id = next;
+        self.modules.insert(id, module);
+        self.update_perf_stats();
+        Ok(id)
+    }
+
+    // The operator of the module is returned, if any
+    pub fn remove_module(
+        &mut self,
+        id: &ShipModuleId,
+    ) -> Result<(ShipModule, Option<(CrewId, CrewMember)>), Errcode> {
+        let Some(module) = self.modules.get(id) else {
+            return Err(Errcode::NoSuchModule(*id));
+        };
+        if self.cargo.usage > (self.cargo.capacity - module.cargo_bonus()) {
+            return Err(Errcode::ShipCargoNotEmpty);
+        }
+        let operator = module
+            .operator
+            .and_then(|cid| self.remove_crew_member(&cid).map(|cm| (cid, cm)));
+        let module = self.modules.remove(id).unwrap();
+        self.update_perf_stats();
+        Ok((module, operator))
+    }
+
+    // The capacities given by the modules are replaced by the new ones
+    fn update_modules_capacity(&mut self, old: &ShipStats) {
+        self.stats.cargo_bonus = self.modules.values().map(|m| m.cargo_bonus()).sum();
+        self.stats.fuel_tank_bonus = self.modules.values().map(|m| m.fuel_tank_bonus()).sum();
+        self.cargo.capacity += self.stats.cargo_bonus - old.cargo_bonus;
+        self.fuel_tank_capacity += self.stats.fuel_tank_bonus - old.fuel_tank_bonus;
+        self.fuel_tank = self.fuel_tank.min(self.fuel_tank_capacity);
+    }
+
+    #[inline]
+    pub fn hull_wear_ratio(&self) -> f64 {
+        if self.hull_decay_capacity <= 0.0 {
+            return 0.0;
+        }
+        (self.hull_decay / self.hull_decay_capacity).clamp(0.0, 1.0)
+    }
+
+    // Highest critical threshold passed since the hull had this wear ratio
+    pub fn hull_threshold_crossed(&self, wear_before: f64) -> Option<f64> {
+        let wear = self.hull_wear_ratio();
+        HULL_CRITICAL_THRESHOLDS
+            .into_iter()
+            .rev()
+            .find(|t| wear_before < *t && wear >= *t)
+    }
+
+    #[inline]
+    pub fn hull_speed_factor(&self) -> f64 {
+        1.0 - (HULL_WEAR_SPEED_MALUS * self.hull_wear_ratio())
+    }
+
+    #[inline]
+    pub fn hull_extraction_factor(&self) -> f64 {
+        1.0 - (HULL_WEAR_EXTRACT_MALUS * self.hull_wear_ratio())
+    }
+
+    // The worn ships lose a part of their value
+    #[inline]
+    pub fn hull_value_factor(&self) -> f64 {
+        1.0 - (HULL_WEAR_VALUE_MALUS * self.hull_wear_ratio())
+    }
+
+    // Raw resources got back by dismantling the ship, with its fuel and cargo
+    pub fn scrap_resources(&self) -> BTreeMap<Resource, f64> {
+        let mut res = self.cargo.resources.clone();
+        let hull_left = self.hull_decay_capacity - self.hull_decay;
+        let copper = self
+            .modules
+            .values()
+            .map(|m| (m.rank as f64) * SCRAP_COPPER_PER_MODULE_RANK)
+            .sum::<f64>();
+        let iron = (self.reactor_power as f64) * SCRAP_IRON_PER_POWER
+            + self.cargo.capacity * SCRAP_IRON_PER_CARGO;
+        for (r, amnt) in [
+            (Resource::HullPlate, hull_left * SCRAP_HULL_PLATE_RATE),
+            (Resource::Iron, iron),
+            (Resource::Copper, copper),
+            (Resource::Fuel, self.fuel_tank),
+        ] {
+            *res.entry(r).or_default() += amnt;
+        }
+        res.retain(|_, amnt| *amnt > 0.0);
+        res
+    }
+
+    // Removes a crew member from the ship, and from its post
+    pub fn remove_crew_member(&mut self, id: &CrewId) -> Option<CrewMember> {
+        let member = self.crew.0.remove(id)?;
+        if self.pilot == Some(*id) {
+            self.pilot = None;
+        }
+        for module in self.modules.values_mut() {
+            if module.operator == Some(*id) {
+                module.operator = None;
+            }
+        }
+        self.update_perf_stats();
+        Some(member)
+    }
+
+    // Sends all the crew out of the ship
+    pub fn unload_crew(&mut self) -> Crew {
+        self.pilot = None;
+        for module in self.modules.values_mut() {
+            module.operator = None;
+        }
+        let crew = std::mem::take(&mut self.crew);
+        self.update_perf_stats();
+        crew
     }
 
     pub fn compute_travel_costs(&self, destination: SpaceCoord) -> Result<TravelCost, Errcode> {
@@ -198,12 +391,13 @@ impl Ship {
         Ok(cost)
     }
 
-    pub fn update_flight(&mut self, mut tdelta: f64) -> bool {
+    pub fn update_flight(&mut self, mut tdelta: f64) -> FlightStatus {
         let ShipState::InFlight(ref mut data) = self.state else {
             unreachable!();
         };
 
         let mut finished = false;
+        let dist_tot = data.dist_tot;
         let mut dist_delta = self.stats.speed * tdelta;
         data.dist_done += dist_delta;
         if data.dist_done > data.dist_tot {
@@ -217,29 +411,70 @@ impl Ship {
             debug_assert!(((tdelta * self.stats.speed) - dist_delta).abs() < 1e-7);
         }
 
-        self.position = translation(data.start, data.direction, data.dist_done);
+        self.position = if finished {
+            data.destination
+        } else {
+            translation(data.start, data.direction, data.dist_done)
+        };
 
         self.fuel_tank -= self.stats.fuel_consumption * tdelta;
-        if self.fuel_tank <= 0.0 {
+        let out_of_fuel = self.fuel_tank <= 0.0;
+        if out_of_fuel {
             self.fuel_tank = 0.0;
             log::debug!("Ship {} has an empty fuel tank", self.id);
-            return true;
         }
 
         self.hull_decay += self.stats.hull_usage_rate * dist_delta;
         if self.hull_decay >= self.hull_decay_capacity {
             log::debug!("Ship {} worn out all its hull", self.id);
-            return true;
+            return FlightStatus::HullWornOut;
         }
+        self.drone_repair(tdelta);
 
         if finished {
-            debug_assert_eq!(self.position, data.destination);
+            self.flight_experience(dist_tot);
+        }
+        // The hull got worn during this step, the ship slows down
+        self.update_perf_stats();
+        if finished {
+            FlightStatus::Arrived
+        } else if out_of_fuel {
+            FlightStatus::OutOfFuel
+        } else {
+            FlightStatus::InFlight
+        }
+    }
+
+    // The repair drones use the hull plates of the cargo
+    fn drone_repair(&mut self, tdelta: f64) {
+        if self.stats.repair_rate <= 0.0 || self.hull_decay <= 0.0 {
+            return;
+        }
+        let amnt = self.hull_decay.min(self.stats.repair_rate * tdelta);
+        let unloaded = self.cargo.unload(&Resource::HullPlate, amnt);
+        self.hull_decay -= unloaded;
+    }
+
+    // Given at the end of the flight so the speed doesn't change along the way
+    fn flight_experience(&mut self, distance: f64) {
+        if let Some(pilot) = self.pilot.and_then(|id| self.crew.0.get_mut(&id)) {
+            pilot.gain_xp(distance);
+        }
+        let low_fuel = self.fuel_tank < (self.fuel_tank_capacity * DANGER_FUEL_RATIO);
+        if low_fuel || self.hull_wear_ratio() > DANGER_HULL_WEAR {
+            for cm in self.crew.0.values_mut() {
+                cm.lose_morale(MORALE_DANGER_LOSS);
+            }
         }
-        finished
     }
 
     pub fn stop_navigation(&mut self) -> Result<SpaceCoord, Errcode> {
         log::debug!("Stopping flight on ship {}", self.id);
+        if let ShipState::InFlight(ref data) = self.state {
+            let dist_done = data.dist_done;
+            self.flight_experience(dist_done);
+            self.update_perf_stats();
+        }
         self.state = ShipState::Idle;
         Ok(self.position)
     }
@@ -261,6 +496,7 @@ impl Ship {
         // TO DO Else, return an error to say that we don't have any module to extract resource on this planet
         if !extraction.0.is_empty() {
             self.state = ShipState::Extracting(extraction.clone());
+            self.extracted.clear();
         }
         log::debug!("Extraction of resources: {extraction:?}");
         Ok(extraction)
@@ -275,11 +511,138 @@ impl Ship {
         Ok(())
     }
 
+    // Fills the fuel tank with the fuel stored in the cargo of the ship
+    pub fn refuel_from_cargo(&mut self) -> Result<f64, Errcode> {
+        let ShipState::Idle = self.state else {
+            return Err(Errcode::ShipNotIdle);
+        };
+        if self
+            .cargo
+            .resources
+            .get(&Resource::Fuel)
+            .is_none_or(|q| *q == 0.0)
+        {
+            return Err(Errcode::NoFuelInCargo);
+        }
+        let needed = (self.fuel_tank_capacity - self.fuel_tank).max(0.0);
+        let unloaded = self.cargo.unload(&Resource::Fuel, needed);
+        self.fuel_tank += unloaded;
+        Ok(unloaded)
+    }
+
+    // The operators on board repair the hull using the hull plates of the cargo,
+    // until the hull is repaired or there are no more plates
+    pub fn start_repair(&mut self) -> Result<RepairInfo, Errcode> {
+        let ShipState::Idle = self.state else {
+            return Err(Errcode::ShipNotIdle);
+        };
+        if self
+            .cargo
+            .resources
+            .get(&Resource::HullPlate)
+            .is_none_or(|q| *q == 0.0)
+        {
+            return Err(Errcode::NoHullPlateInCargo);
+        }
+        let rate: f64 = self
+            .crew
+            .0
+            .values()
+            .filter(|cm| cm.member_type == CrewMemberType::Operator)
+            .map(|cm| SELF_REPAIR_RATE * (cm.rank as f64).sqrt() * cm.performance())
+            .sum();
+        if rate == 0.0 {
+            return Err(Errcode::NoOperatorOnBoard);
+        }
+        log::debug!("Ship {} started repairing at {rate}/sec", self.id);
+        let repair = RepairInfo { rate };
+        self.state = ShipState::Repairing(repair.clone());
+        Ok(repair)
+    }
+
+    pub fn stop_repair(&mut self) -> Result<(), Errcode> {
+        let ShipState::Repairing(_) = self.state else {
+            return Err(Errcode::ShipNotRepairing);
+        };
+        log::debug!("Ship {} stopped repairing", self.id);
+        self.state = ShipState::Idle;
+        self.update_perf_stats();
+        Ok(())
+    }
+
+    pub fn update_repair(&mut self, tdelta: f64) -> bool {
+        let ShipState::Repairing(ref repair) = self.state else {
+            unreachable!();
+        };
+        let amnt = self.hull_decay.min(repair.rate * tdelta);
+        self.hull_decay -= self.cargo.unload(&Resource::HullPlate, amnt);
+        let plates = self.cargo.resources.get(&Resource::HullPlate);
+        let finished = self.hull_decay <= 0.0 || plates.is_none_or(|q| *q <= 0.0);
+        if finished {
+            self.update_perf_stats();
+        }
+        finished
+    }
+
+    // The ship doesn't move until the tow service brings it to the station
+    pub fn update_tow(&mut self, tdelta: f64) -> bool {
+        let ShipState::Towed(ref mut tow) = self.state else {
+            unreachable!();
+        };
+        tow.remaining -= tdelta;
+        if tow.remaining > 0.0 {
+            return false;
+        }
+        log::debug!("Ship {} towed to station {}", self.id, tow.station);
+        self.position = tow.destination;
+        true
+    }
+
     pub fn update_extract(&mut self, tdelta: f64) -> bool {
         let ShipState::Extracting(ref rates) = self.state else {
             unreachable!();
         };
-        rates.update_cargo(&mut self.cargo, tdelta)
+        let usage = self.cargo.usage;
+        let full = rates.update_cargo(&mut self.cargo, &mut self.extracted, tdelta);
+
+        // The experience is shared between the operators extracting
+        let operators: Vec<CrewId> = self
+            .modules
+            .values()
+            .filter(|m| m.modtype != ShipModuleType::Scanner)
+            .filter_map(|m| m.operator)
+            .collect();
+        let volume = (self.cargo.usage - usage) / (operators.len().max(1) as f64);
+        for id in operators {
+            if let Some(cm) = self.crew.0.get_mut(&id) {
+                cm.gain_xp(volume);
+            }
+        }
+        full
+    }
+
+    // Rank of the best scanner module having an operator assigned
+    pub fn scanner_rank(&self) -> Option<u8> {
+        self.modules
+            .values()
+            .filter(|m| m.modtype == ShipModuleType::Scanner && m.operator.is_some())
+            .map(|m| m.rank)
+            .max()
+    }
+
+    // Discovers the sectors in range of the scanner before scanning them
+    pub async fn scan(
+        &self,
+        galaxy: &mut Galaxy,
+        ships: &[ShipInfo],
+    ) -> Result<ScanResult, Errcode> {
+        let Some(rank) = self.scanner_rank() else {
+            return Err(Errcode::NoScanner);
+        };
+        let first_discovered = galaxy.discover_around(rank, &self.position);
+        let mut results = galaxy.scan_sector(rank, &self.position, ships).await;
+        results.first_discovered = first_discovered;
+        Ok(results)
     }
 
     pub fn unload_cargo(
@@ -305,6 +668,7 @@ impl Ship {
 
 #[test]
 fn test_ship_flight() {
+    use rand::Rng;
     crate::tests::create_property_based_test(100000, &[], |rng| {
         let (x, y, z) = (rng.random(), rng.random(), rng.random());
         let mut ship = Ship::random((x, y, z));
@@ -330,9 +694,7 @@ fn test_ship_flight() {
             assert!(costs.duration > 0.0);
             ship.update_flight(costs.duration / 2.0);
             let ShipState::InFlight(flight) = ship.state else {
-                println!("Ship not in flight: {:?}", ship.state);
-                assert!(false);
-                unreachable!();
+                panic!("Ship not in flight: {:?}", ship.state);
             };
             assert_eq!(flight.start, (x, y, z));
             assert_eq!(flight.destination, dest);
@@ -356,3 +718,150 @@ fn test_ship_flight() {
         // TO DO Check distance
     });
 }
+
+#[test]
+fn test_pilot_rank_curves() {
+    let max = CrewMemberType::Pilot.max_rank();
+    assert_eq!(pilot_fuel_factor(1), 1.0);
+    assert_eq!(pilot_speed_factor(1), 1.0);
+    for rank in 2..=max {
+        let (fuel, prev_fuel) = (pilot_fuel_factor(rank), pilot_fuel_factor(rank - 1));
+        let (speed, prev_speed) = (pilot_speed_factor(rank), pilot_speed_factor(rank - 1));
+        assert!(fuel < prev_fuel);
+        assert!(fuel > 1.0 - PILOT_FUEL_SAVING_MAX);
+        assert!(speed > prev_speed && speed.is_finite());
+        if rank > 2 {
+            // Diminishing returns
+            assert!(prev_fuel - fuel < pilot_fuel_factor(rank - 2) - prev_fuel);
+            assert!(speed - prev_speed < prev_speed - pilot_speed_factor(rank - 2));
+        }
+    }
+
+    crate::tests::create_property_based_test(1000, &[], |rng| {
+        use rand::Rng;
+        let mut ship = Ship::random((0, 0, 0));
+        let mut pilot = CrewMember::from(CrewMemberType::Pilot);
+        pilot.rank = rng.random_range(1..=max);
+        pilot.xp = rng.random_range(0.0..1e6);
+        pilot.morale = rng.random_range(0.0..=1.0);
+        ship.crew.0.insert(0, pilot);
+        ship.pilot = Some(0);
+        ship.update_perf_stats();
+        assert!(ship.stats.fuel_consumption > 0.0);
+        assert!(ship.stats.speed > 0.0 && ship.stats.speed.is_finite());
+    });
+}
+
+#[test]
+fn test_equipment_modules() {
+    let mut ship = Ship::random((0, 0, 0));
+    ship.modules.clear();
+    ship.update_perf_stats();
+    let (cargo_cap, fuel_cap) = (ship.cargo.capacity, ship.fuel_tank_capacity);
+    let price = ship.compute_price(1.0);
+
+    ship.modules
+        .insert(1, ShipModuleType::CargoPod.new_module());
+    ship.modules
+        .insert(2, ShipModuleType::FuelTank.new_module());
+    ship.update_perf_stats();
+    assert!(ship.cargo.capacity > cargo_cap);
+    assert!(ship.fuel_tank_capacity > fuel_cap);
+    // The capacities given by the modules are paid with the modules
+    let modules = ship.modules.values().map(|m| m.value(1.0)).sum::<f64>();
+    assert!(modules > 0.0);
+    assert!((ship.compute_price(1.0) - (price + modules)).abs() < 1e-6);
+    let pod_cap = ship.cargo.capacity;
+    ship.modules.get_mut(&1).unwrap().rank_up().unwrap();
+    ship.update_perf_stats();
+    assert!(ship.cargo.capacity > pod_cap);
+    ship.modules.clear();
+    ship.update_perf_stats();
+    assert!((ship.cargo.capacity - cargo_cap).abs() < 1e-9);
+    assert!((ship.fuel_tank_capacity - fuel_cap).abs() < 1e-9);
+
+    ship.crew
+        .0
+        .insert(0, CrewMember::from(CrewMemberType::Pilot));
+    ship.pilot = Some(0);
+    ship.update_perf_stats();
+    let (speed, fuel) = (ship.stats.speed, ship.stats.fuel_consumption);
+    ship.modules
+        .insert(1, ShipModuleType::Afterburner.new_module());
+    ship.modules
+        .insert(2, ShipModuleType::RepairDrone.new_module());
+    ship.update_perf_stats();
+    assert!(ship.stats.speed > speed);
+    assert!(ship.stats.fuel_consumption > fuel);
+
+    ship.hull_decay = 100.0;
+    ship.cargo.add_resource(&Resource::HullPlate, 10.0);
+    ship.drone_repair(100.0);
+    assert_eq!(ship.hull_decay, 90.0);
+    assert!(ship.cargo.resources[&Resource::HullPlate] == 0.0);
+}
+
+#[test]
+fn test_self_repair_and_refuel() {
+    use crate::tests::{board_crew, fixed_ship};
+
+    let mut ship = fixed_ship(1, (0, 0, 0));
+    ship.fuel_tank = 0.0;
+    ship.cargo.add_resource(&Resource::Fuel, 10.0);
+    assert_eq!(ship.refuel_from_cargo().unwrap(), 10.0);
+    assert_eq!(ship.fuel_tank, 10.0);
+    assert!(matches!(
+        ship.refuel_from_cargo(),
+        Err(Errcode::NoFuelInCargo)
+    ));
+
+    ship.hull_decay = 100.0;
+    ship.cargo.add_resource(&Resource::HullPlate, 50.0);
+    assert!(matches!(
+        ship.start_repair(),
+        Err(Errcode::NoOperatorOnBoard)
+    ));
+    board_crew(&mut ship, 0, CrewMemberType::Operator);
+    let repair = ship.start_repair().unwrap();
+    assert!(matches!(
+        ship.refuel_from_cargo(),
+        Err(Errcode::ShipNotIdle)
+    ));
+    assert!(!ship.update_repair(1.0));
+    assert!((ship.hull_decay - (100.0 - repair.rate)).abs() < 1e-9);
+
+    // Stops once the hull plates are all used
+    while !ship.update_repair(1.0) {}
+    assert_eq!(ship.hull_decay, 50.0);
+    assert!(ship.stop_repair().is_ok());
+    assert!(matches!(ship.stop_repair(), Err(Errcode::ShipNotRepairing)));
+}
+
+#[test]
+fn test_hull_thresholds() {
+    let mut ship = Ship::random((0, 0, 0));
+    ship.hull_decay = ship.hull_decay_capacity * 0.85;
+    assert_eq!(ship.hull_threshold_crossed(0.4), Some(0.8));
+    assert_eq!(ship.hull_threshold_crossed(0.8), None);
+    ship.hull_decay = ship.hull_decay_capacity * 0.96;
+    assert_eq!(ship.hull_threshold_crossed(0.85), Some(0.95));
+}
+
+#[test]
+fn test_module_ids_exhausted() {
+    let mut ship = crate::tests::fixed_ship(1, (0, 0, 0));
+    ship.next_module_id = ShipModuleId::MAX - 1;
+    let id = ship
+        .add_module(ShipModuleType::Scanner.new_module())
+        .unwrap();
+    assert_eq!(id, ShipModuleId::MAX - 1);
+    assert!(matches!(
+        ship.add_module(ShipModuleType::Scanner.new_module()),
+        Err(Errcode::NoModuleIdLeft)
+    ));
+    ship.modules.clear();
+    assert!(matches!(
+        ship.add_module(ShipModuleType::Scanner.new_module()),
+        Err(Errcode::NoModuleIdLeft)
+    ));
+}
@@ -0,0 +1,273 @@
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
+
+use super::cargo::ShipCargo;
+use super::module::{ShipModule, ShipModuleType};
+use super::Ship;
+use crate::galaxy::SpaceCoord;
+
+// Number of ships on sale in a shipyard
+pub const SHIPYARD_SIZE: usize = 4;
+// Game seconds between two renewals of the shipyards offers
+pub const SHIPYARD_REFRESH_SEC: f64 = 600.0;
+
+// Each rank of the station trader improves the offers as much as this
+const TRADER_RANK_QUALITY: f64 = 0.1;
+// The size of the starter ships is taken in the lowest part of the range
+const STARTER_SIZE_MAX: f64 = 0.3;
+// Each stat can differ a bit from the size of the ship
+const STAT_NOISE: f64 = 0.1;
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, EnumIter, IntoStaticStr,
+)]
+pub enum HullClass {
+    #[default]
+    Shuttle,
+    Hauler,
+    Prospector,
+    Explorer,
+    Frigate,
+}
+
+// Range of the specs of a hull class, the stats of a ship grow together
+struct HullProfile {
+    reactor_power: (f64, f64),
+    fuel_tank_capacity: (f64, f64),
+    cargo_capacity: (f64, f64),
+    hull_decay_capacity: (f64, f64),
+    shield_power: (f64, f64),
+    modules: &'static [ShipModuleType],
+    nb_modules: usize,
+    module_slots: u8,
+}
+
+impl HullClass {
+    fn profile(&self) -> HullProfile {
+        match self {
+            HullClass::Shuttle => HullProfile {
+                reactor_power: (1.0, 2.0),
+                fuel_tank_capacity: (800.0, 1200.0),
+                cargo_capacity: (150.0, 250.0),
+                hull_decay_capacity: (2500.0, 4000.0),
+                shield_power: (0.0, 0.0),
+                modules: &[],
+                nb_modules: 0,
+                module_slots: 2,
+            },
+            HullClass::Hauler => HullProfile {
+                reactor_power: (2.0, 4.0),
+                fuel_tank_capacity: (1500.0, 2500.0),
+                cargo_capacity: (600.0, 1500.0),
+                hull_decay_capacity: (5000.0, 9000.0),
+                shield_power: (0.0, 1.0),
+                modules: &[],
+                nb_modules: 0,
+                module_slots: 3,
+            },
+            HullClass::Prospector => HullProfile {
+                reactor_power: (2.0, 5.0),
+                fuel_tank_capacity: (1500.0, 3000.0),
+                cargo_capacity: (300.0, 700.0),
+                hull_decay_capacity: (5000.0, 10000.0),
+                shield_power: (0.0, 1.0),
+                modules: &[ShipModuleType::Miner, ShipModuleType::GasSucker],
+                nb_modules: 1,
+                module_slots: 4,
+            },
+            HullClass::Explorer => HullProfile {
+                reactor_power: (3.0, 6.0),
+                fuel_tank_capacity: (3000.0, 6000.0),
+                cargo_capacity: (100.0, 300.0),
+                hull_decay_capacity: (3000.0, 6000.0),
+                shield_power: (0.0, 1.0),
+                modules: &[ShipModuleType::Scanner],
+                nb_modules: 1,
+                module_slots: 4,
+            },
+            HullClass::Frigate => HullProfile {
+                reactor_power: (6.0, 12.0),
+                fuel_tank_capacity: (3000.0, 5000.0),
+                cargo_capacity: (800.0, 1500.0),
+                hull_decay_capacity: (15000.0, 25000.0),
+                shield_power: (2.0, 4.0),
+                modules: &[ShipModuleType::Miner, ShipModuleType::GasSucker],
+                nb_modules: 2,
+                module_slots: 6,
+            },
+        }
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    EnumIter,
+    IntoStaticStr,
+)]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Legendary,
+}
+
+impl Rarity {
+    // Upper bound of the roll to get this rarity, for a neutral quality
+    fn threshold(&self) -> f64 {
+        match self {
+            Rarity::Common => 0.6,
+            Rarity::Uncommon => 0.85,
+            Rarity::Rare => 0.97,
+            Rarity::Legendary => 1.0,
+        }
+    }
+
+    // Multiplier on the specs of the ship
+    pub fn stats_factor(&self) -> f64 {
+        match self {
+            Rarity::Common => 1.0,
+            Rarity::Uncommon => 1.15,
+            Rarity::Rare => 1.35,
+            Rarity::Legendary => 1.6,
+        }
+    }
+
+    // Rank of the modules installed on the ship
+    fn module_rank(&self) -> u8 {
+        match self {
+            Rarity::Common | Rarity::Uncommon => 1,
+            Rarity::Rare => 2,
+            Rarity::Legendary => 3,
+        }
+    }
+
+    // A higher quality makes the rare ships more likely, 1.0 is neutral
+    pub fn roll<R: Rng>(rng: &mut R, quality: f64) -> Rarity {
+        let roll = rng.random::<f64>().powf(1.0 / quality.max(1.0));
+        Rarity::iter()
+            .find(|r| roll < r.threshold())
+            .unwrap_or(Rarity::Legendary)
+    }
+}
+
+// Quality of the offers of a shipyard
+pub fn shipyard_quality(station_quality: f64, trader_rank: u8) -> f64 {
+    1.0 + station_quality + (trader_rank as f64) * TRADER_RANK_QUALITY
+}
+
+fn in_range<R: Rng>(rng: &mut R, (min, max): (f64, f64), size: f64) -> f64 {
+    let t = (size + rng.random_range(-STAT_NOISE..=STAT_NOISE)).clamp(0.0, 1.0);
+    min + (max - min) * t
+}
+
+fn preinstalled_module(modtype: ShipModuleType, rank: u8) -> ShipModule {
+    ShipModule {
+        rank,
+        ..modtype.new_module()
+    }
+}
+
+pub fn generate<R: Rng>(
+    rng: &mut R,
+    position: SpaceCoord,
+    class: HullClass,
+    rarity: Rarity,
+    size: f64,
+) -> Ship {
+    let profile = class.profile();
+    let fact = rarity.stats_factor();
+
+    let reactor_power = (in_range(rng, profile.reactor_power, size) * fact).round() as u16;
+    let shield_power = in_range(rng, profile.shield_power, size).round() as u16;
+    let cargo_cap = (in_range(rng, profile.cargo_capacity, size) * fact).round();
+
+    let mut nb_modules = profile.nb_modules;
+    if rarity == Rarity::Legendary && !profile.modules.is_empty() {
+        nb_modules += 1;
+    }
+    let mut ship = Ship {
+        id: rng.random(),
+        position,
+        hull_class: class,
+        rarity,
+        reactor_power: reactor_power.max(1),
+        fuel_tank_capacity: (in_range(rng, profile.fuel_tank_capacity, size) * fact).round(),
+        cargo: ShipCargo::with_capacity(cargo_cap),
+        hull_decay_capacity: (in_range(rng, profile.hull_decay_capacity, size) * fact).round(),
+        shield_power: shield_power + (rarity as u16) / 2,
+        module_slots: profile.module_slots + (rarity as u8) / 2,
+        ..Default::default()
+    };
+    for _ in 0..nb_modules {
+        let modtype = profile.modules.choose(rng).unwrap().clone();
+        if ship
+            .add_module(preinstalled_module(modtype, rarity.module_rank()))
+            .is_err()
+        {
+            break;
+        }
+    }
+    ship
+}
+
+// A cheap ship, always available so a new player can start playing
+pub fn starter<R: Rng>(rng: &mut R, position: SpaceCoord) -> Ship {
+    let size = rng.random_range(0.0..STARTER_SIZE_MAX);
+    generate(rng, position, HullClass::Shuttle, Rarity::Common, size)
+}
+
+pub fn random_offer<R: Rng>(rng: &mut R, position: SpaceCoord, quality: f64) -> Ship {
+    let class = *HullClass::iter()
+        .collect::<Vec<HullClass>>()
+        .choose(rng)
+        .unwrap();
+    let rarity = Rarity::roll(rng, quality);
+    let size = rng.random_range(0.0..=1.0);
+    generate(rng, position, class, rarity, size)
+}
+
+pub fn shipyard_offers<R: Rng>(rng: &mut R, position: SpaceCoord, quality: f64) -> Vec<Ship> {
+    let mut offers = vec![starter(rng, position)];
+    while offers.len() < SHIPYARD_SIZE {
+        offers.push(random_offer(rng, position, quality));
+    }
+    offers
+}
+
+#[test]
+fn test_ship_generation() {
+    crate::tests::create_property_based_test(1000, &[], |rng| {
+        let class = *HullClass::iter()
+            .collect::<Vec<HullClass>>()
+            .choose(rng)
+            .unwrap();
+        let small = generate(rng, (0, 0, 0), class, Rarity::Common, 0.0);
+        let big = generate(rng, (0, 0, 0), class, Rarity::Common, 1.0);
+        let rare = generate(rng, (0, 0, 0), class, Rarity::Legendary, 1.0);
+        assert!(small.reactor_power >= 1);
+        assert!(small.compute_price(1.0) < big.compute_price(1.0));
+        assert!(big.compute_price(1.0) < rare.compute_price(1.0));
+        assert!(big.modules.len() <= rare.modules.len());
+        assert!(rare.modules.values().all(|m| m.rank == 3));
+    });
+
+    let mut rng = rand::rng();
+    let common = |rng: &mut rand::rngs::ThreadRng, quality: f64| {
+        (0..10000)
+            .filter(|_| Rarity::roll(rng, quality) == Rarity::Common)
+            .count()
+    };
+    assert!(common(&mut rng, 1.0) > common(&mut rng, 3.0));
+}
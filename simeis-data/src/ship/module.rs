@@ -2,7 +2,8 @@ use serde::{Deserialize, Serialize};
 use strum::{EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
 
 use super::resources::Resource;
-use crate::crew::{Crew, CrewId, CrewMemberType};
+use crate::crew::{Crew, CrewId, CrewMemberType, CrewTrait};
+use crate::errors::Errcode;
 use crate::galaxy::planet::Planet;
 
 const MOD_UPG_POWF_DIV: f64 = 75.0;
@@ -10,6 +11,16 @@ const EXTRACTION_RATE_RANK_POWF: f64 = 0.45;
 const EXRATE_DIFF_FACT: f64 = 2.5;
 const EXRATE_FACT: f64 = 0.6;
 
+// The effect of the equipment modules grows with rank ^ this
+const MOD_EFFECT_RANK_POWF: f64 = 0.8;
+const CARGO_POD_CAPACITY: f64 = 150.0;
+const FUEL_TANK_CAPACITY: f64 = 800.0;
+// Hull repaired per second, consuming the same amount of hull plates
+const REPAIR_DRONE_RATE: f64 = 2.0;
+const AFTERBURNER_RANK_POWF: f64 = 0.5;
+const AFTERBURNER_SPEED: f64 = 0.25;
+const AFTERBURNER_FUEL: f64 = 0.4;
+
 pub type ShipModuleId = u16;
 
 #[derive(
@@ -29,6 +40,11 @@ pub type ShipModuleId = u16;
 pub enum ShipModuleType {
     Miner,
     GasSucker,
+    Scanner,
+    CargoPod,
+    FuelTank,
+    RepairDrone,
+    Afterburner,
 }
 
 impl ShipModuleType {
@@ -36,17 +52,53 @@ impl ShipModuleType {
         ShipModule {
             operator: None,
             modtype: self,
-            totalcost: 0.0,
             rank: 1,
         }
     }
 
+    pub fn max_rank(&self) -> u8 {
+        match self {
+            ShipModuleType::Miner | ShipModuleType::GasSucker => 20,
+            _ => 10,
+        }
+    }
+
+    // Number of module slots of the ship taken by this module
+    pub fn slot_size(&self) -> u8 {
+        match self {
+            ShipModuleType::Miner | ShipModuleType::GasSucker | ShipModuleType::Afterburner => 2,
+            _ => 1,
+        }
+    }
+
+    // Modules that need an operator to work
+    pub fn operated(&self) -> bool {
+        matches!(
+            self,
+            ShipModuleType::Miner | ShipModuleType::GasSucker | ShipModuleType::Scanner
+        )
+    }
+
+    pub fn extracts(&self) -> bool {
+        matches!(self, ShipModuleType::Miner | ShipModuleType::GasSucker)
+    }
+
     #[inline]
-    pub fn get_price_buy(&self) -> f64 {
+    fn base_price(&self) -> f64 {
         match self {
             ShipModuleType::Miner | ShipModuleType::GasSucker => 4500.0,
+            ShipModuleType::Scanner => 6000.0,
+            ShipModuleType::CargoPod => 3000.0,
+            ShipModuleType::FuelTank => 2500.0,
+            ShipModuleType::RepairDrone => 8000.0,
+            ShipModuleType::Afterburner => 7000.0,
         }
     }
+
+    #[inline]
+    pub fn get_price_buy(&self, inflation: f64) -> f64 {
+        self.base_price() * inflation
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -54,23 +106,79 @@ pub struct ShipModule {
     pub operator: Option<CrewId>,
     pub modtype: ShipModuleType,
     pub rank: u8,
-    pub totalcost: f64,
 }
 
 impl ShipModule {
     #[inline]
-    pub fn price_next_rank(&self) -> f64 {
+    pub fn price_next_rank(&self, inflation: f64) -> f64 {
         let num = MOD_UPG_POWF_DIV - 1.0 + (self.rank as f64);
-        self.modtype.get_price_buy().powf(num / MOD_UPG_POWF_DIV)
+        self.modtype.base_price().powf(num / MOD_UPG_POWF_DIV) * inflation
+    }
+
+    // Price paid for the module and its ranks, without the local rates
+    pub fn value(&self, inflation: f64) -> f64 {
+        let mut module = self.modtype.clone().new_module();
+        let mut value = self.modtype.get_price_buy(inflation);
+        while module.rank < self.rank {
+            value += module.price_next_rank(inflation);
+            module.rank += 1;
+        }
+        value
+    }
+
+    pub fn rank_up(&mut self) -> Result<u8, Errcode> {
+        let max = self.modtype.max_rank();
+        if self.rank >= max {
+            return Err(Errcode::MaxRankReached(max));
+        }
+        self.rank += 1;
+        Ok(self.rank)
+    }
+
+    #[inline]
+    fn effect(&self, modtype: ShipModuleType, base: f64, powf: f64) -> f64 {
+        if self.modtype != modtype {
+            return 0.0;
+        }
+        base * (self.rank as f64).powf(powf)
+    }
+
+    pub fn cargo_bonus(&self) -> f64 {
+        self.effect(
+            ShipModuleType::CargoPod,
+            CARGO_POD_CAPACITY,
+            MOD_EFFECT_RANK_POWF,
+        )
+    }
+
+    pub fn fuel_tank_bonus(&self) -> f64 {
+        self.effect(
+            ShipModuleType::FuelTank,
+            FUEL_TANK_CAPACITY,
+            MOD_EFFECT_RANK_POWF,
+        )
+    }
+
+    pub fn repair_rate(&self) -> f64 {
+        self.effect(
+            ShipModuleType::RepairDrone,
+            REPAIR_DRONE_RATE,
+            MOD_EFFECT_RANK_POWF,
+        )
+    }
+
+    // Multipliers on the speed and on the fuel consumption
+    pub fn afterburner(&self) -> (f64, f64) {
+        let boost = self.effect(ShipModuleType::Afterburner, 1.0, AFTERBURNER_RANK_POWF);
+        (
+            1.0 + (boost * AFTERBURNER_SPEED),
+            1.0 + (boost * AFTERBURNER_FUEL),
+        )
     }
 
     // Returns
     pub fn need(&self, ctype: &CrewMemberType) -> bool {
-        match self.modtype {
-            ShipModuleType::Miner | ShipModuleType::GasSucker => {
-                ctype == &CrewMemberType::Operator && self.operator.is_none()
-            }
-        }
+        self.modtype.operated() && ctype == &CrewMemberType::Operator && self.operator.is_none()
     }
 
     pub fn can_extract(&self, crew: &Crew, planet: &Planet) -> Vec<(Resource, f64)> {
@@ -80,6 +188,7 @@ impl ShipModule {
         };
 
         let cm = crew.0.get(opid).unwrap();
+        let perf = cm.performance() * cm.trait_factor(CrewTrait::extraction_factor);
         let all_resources = Resource::iter()
             .map(|r| (r, planet.resource_density(&r)))
             .filter(|(_, d)| *d > 0.0);
@@ -87,12 +196,13 @@ impl ShipModule {
         match self.modtype {
             ShipModuleType::Miner => all_resources
                 .filter(|(r, _)| r.mineable(cm.rank))
-                .map(|(r, density)| (r, self.extraction_rate(&r, cm.rank, density)))
+                .map(|(r, density)| (r, self.extraction_rate(&r, cm.rank, density) * perf))
                 .collect(),
             ShipModuleType::GasSucker => all_resources
                 .filter(|(r, _)| r.suckable(cm.rank))
-                .map(|(r, density)| (r, self.extraction_rate(&r, cm.rank, density)))
+                .map(|(r, density)| (r, self.extraction_rate(&r, cm.rank, density) * perf))
                 .collect(),
+            _ => vec![],
         }
     }
 
@@ -102,3 +212,31 @@ impl ShipModule {
         density * (rank / difficulty).powf(EXRATE_FACT)
     }
 }
+
+#[test]
+fn test_extraction_rank_curves() {
+    crate::tests::create_property_based_test(100, &[], |rng| {
+        use rand::Rng;
+        let density = rng.random_range(0.01..1.0);
+        for modtype in [ShipModuleType::Miner, ShipModuleType::GasSucker] {
+            let mut module = modtype.clone().new_module();
+            let max_op = CrewMemberType::Operator.max_rank();
+            for resource in Resource::iter().filter(|r| !r.scored()) {
+                if !(resource.mineable(max_op) || resource.suckable(max_op)) {
+                    continue;
+                }
+                for modrank in 1..=modtype.max_rank() {
+                    module.rank = modrank;
+                    let mut prev = 0.0;
+                    for oprank in (resource.min_rank() + 1)..=max_op {
+                        let rate = module.extraction_rate(&resource, oprank, density);
+                        assert!(rate.is_finite() && rate > prev);
+                        prev = rate;
+                    }
+                }
+            }
+            module.rank = modtype.max_rank();
+            assert!(matches!(module.rank_up(), Err(Errcode::MaxRankReached(_))));
+        }
+    });
+}
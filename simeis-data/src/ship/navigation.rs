@@ -5,7 +5,7 @@ use crate::{
     galaxy::{get_delta, get_direction, get_distance, SpaceCoord},
 };
 
-use super::Ship;
+use super::{Ship, HULL_WEAR_SPEED_MALUS};
 
 #[derive(Serialize, Deserialize)]
 pub struct Travel {
@@ -26,7 +26,7 @@ impl Travel {
         }
 
         let direction = get_direction(&ship.position, &self.destination);
-        let time_secs = distance / ship.stats.speed;
+        let time_secs = flight_duration(ship, distance);
         let fuel_consumption = ship.stats.fuel_consumption * time_secs;
         let hull_usage = ship.stats.hull_usage_rate * distance;
 
@@ -40,6 +40,24 @@ impl Travel {
     }
 }
 
+// The speed decreases linearly with the distance flown, as the hull gets worn.
+// Integrates 1 / speed over the trip, until the hull wear reaches its maximal malus
+fn flight_duration(ship: &Ship, distance: f64) -> f64 {
+    let factor = ship.hull_speed_factor();
+    let min_factor = 1.0 - HULL_WEAR_SPEED_MALUS;
+    let slope = HULL_WEAR_SPEED_MALUS * ship.stats.hull_usage_rate / ship.hull_decay_capacity;
+    if !slope.is_finite() || slope <= 0.0 || factor <= min_factor {
+        return distance / ship.stats.speed;
+    }
+
+    let base_speed = ship.stats.speed / factor;
+    let dist_wearing = distance.min((factor - min_factor) / slope);
+    let end_factor = factor - (slope * dist_wearing);
+    let t_wearing = (factor / end_factor).ln() / (base_speed * slope);
+    let t_worn = (distance - dist_wearing) / (base_speed * min_factor);
+    t_wearing + t_worn
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TravelCost {
     pub direction: (f64, f64, f64),
@@ -56,6 +74,15 @@ impl TravelCost {
     }
 }
 
+// Outcome of a step of the flight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlightStatus {
+    InFlight,
+    Arrived,
+    OutOfFuel,
+    HullWornOut,
+}
+
 #[derive(Deserialize, Clone, Serialize, Debug)]
 pub struct FlightData {
     pub start: SpaceCoord,
@@ -87,10 +114,7 @@ fn test_compute_cost_addition() {
         let mut ship = Ship::random((0, 0, 0));
         ship.crew.0.insert(
             0,
-            crate::crew::CrewMember {
-                member_type: crate::crew::CrewMemberType::Pilot,
-                rank: 1,
-            },
+            crate::crew::CrewMember::from(crate::crew::CrewMemberType::Pilot),
         );
         ship.pilot = Some(0);
         ship.update_perf_stats();
@@ -106,12 +130,13 @@ fn test_compute_cost_addition() {
                 c2.distance - ((x as f64) * c1.distance) < EPS,
                 "Wrong {x}x distance"
             );
+            // The hull wear slows the ship down along the way
             assert!(
-                c2.duration - ((x as f64) * c1.duration) < EPS,
+                c2.duration - ((x as f64) * c1.duration) > -EPS,
                 "Wrong {x}x duration"
             );
             assert!(
-                c2.fuel_consumption - ((x as f64) * c1.fuel_consumption) < EPS,
+                c2.fuel_consumption - ((x as f64) * c1.fuel_consumption) > -EPS,
                 "Wrong {x}x consumption"
             );
             assert!(
@@ -125,10 +150,7 @@ fn test_compute_cost_addition() {
     let mut ship = Ship::random((0, 0, 0));
     ship.crew.0.insert(
         0,
-        crate::crew::CrewMember {
-            member_type: crate::crew::CrewMemberType::Pilot,
-            rank: 1,
-        },
+        crate::crew::CrewMember::from(crate::crew::CrewMemberType::Pilot),
     );
     ship.pilot = Some(0);
     ship.update_perf_stats();
@@ -137,15 +159,83 @@ fn test_compute_cost_addition() {
     let c3 = ship.compute_travel_costs((10, 10, 10)).unwrap();
 
     ship.position = (5, 5, 5);
+    ship.hull_decay += c1.hull_usage;
+    ship.update_perf_stats();
     let c2 = ship.compute_travel_costs((10, 10, 10)).unwrap();
 
     assert_eq!(c1.distance + c2.distance, c3.distance);
-    assert_eq!(c1.duration + c2.duration, c3.duration);
-    assert_eq!(
-        c1.fuel_consumption + c2.fuel_consumption,
-        c3.fuel_consumption
-    );
+    assert!((c1.duration + c2.duration - c3.duration).abs() < 1e-9);
+    assert!((c1.fuel_consumption + c2.fuel_consumption - c3.fuel_consumption).abs() < 1e-9);
     assert_eq!(c1.hull_usage + c2.hull_usage, c3.hull_usage);
     assert_eq!(c1.direction, c2.direction);
     assert_eq!(c1.direction, c3.direction);
 }
+
+#[test]
+fn test_travel_duration_hull_wear() {
+    use rand::Rng;
+    crate::tests::create_property_based_test(1000, &[], |rng| {
+        let mut ship = Ship::random((0, 0, 0));
+        ship.fuel_tank = f64::MAX;
+        ship.hull_decay = rng.random_range(0.0..(ship.hull_decay_capacity / 2.0));
+        ship.crew.0.insert(
+            0,
+            crate::crew::CrewMember {
+                rank: rng.random_range(1..10),
+                ..crate::crew::CrewMember::from(crate::crew::CrewMemberType::Pilot)
+            },
+        );
+        ship.pilot = Some(0);
+        ship.update_perf_stats();
+        let hull_before = ship.hull_decay;
+
+        let d = rng.random_range(100..5000);
+        let Ok(cost) = ship.set_travel((d, d, d)) else {
+            return;
+        };
+        let mut t = 0.0;
+        while ship.update_flight(0.02) == FlightStatus::InFlight {
+            t += 0.02;
+        }
+        assert_eq!(ship.position, (d, d, d));
+        assert!(
+            (t - cost.duration).abs() <= 0.02 + (cost.duration / 1000.0),
+            "Predicted {}, got {t}",
+            cost.duration
+        );
+        assert!((ship.hull_decay - hull_before - cost.hull_usage).abs() < 1e-6);
+    });
+}
+
+#[test]
+fn test_arrival_with_empty_tank() {
+    let mut ship = Ship::random((0, 0, 0));
+    ship.crew.0.insert(
+        0,
+        crate::crew::CrewMember::from(crate::crew::CrewMemberType::Pilot),
+    );
+    ship.pilot = Some(0);
+    ship.hull_decay = 0.0;
+    ship.fuel_tank = f64::MAX;
+    ship.update_perf_stats();
+    let cost = ship.set_travel((50, 50, 50)).unwrap();
+
+    // The last drop of fuel is burnt on the last step of the flight
+    ship.fuel_tank = cost.fuel_consumption / 2.0;
+    assert_eq!(
+        ship.update_flight(cost.duration * 2.0),
+        FlightStatus::Arrived
+    );
+    assert_eq!(ship.position, (50, 50, 50));
+    assert_eq!(ship.fuel_tank, 0.0);
+    assert!(ship.hull_decay > 0.0);
+    assert!(ship.crew.0[&0].xp > 0.0 || ship.crew.0[&0].rank > 1);
+
+    ship.state = super::ShipState::Idle;
+    ship.fuel_tank = f64::MAX;
+    let cost = ship.set_travel((100, 100, 100)).unwrap();
+    ship.fuel_tank = cost.fuel_consumption / 2.0;
+    let status = ship.update_flight(cost.duration * 0.75);
+    assert_eq!(status, FlightStatus::OutOfFuel);
+    assert_ne!(ship.position, (100, 100, 100));
+}
@@ -115,8 +115,10 @@ pub struct ExtractionInfo(pub BTreeMap<Resource, f64>);
 impl ExtractionInfo {
     pub fn create(ship: &Ship, planet: &Planet) -> Self {
         let mut extraction = BTreeMap::new();
-        for (_, smod) in ship.modules.iter() {
+        let hull_factor = ship.hull_extraction_factor();
+        for smod in ship.modules.values().filter(|m| m.modtype.extracts()) {
             for (res, rate) in smod.can_extract(&ship.crew, planet) {
+                let rate = rate * hull_factor;
                 if let Some(rrate) = extraction.get_mut(&res) {
                     *rrate += rate;
                 } else {
@@ -127,9 +129,15 @@ impl ExtractionInfo {
         ExtractionInfo(extraction)
     }
 
-    pub fn update_cargo(&self, cargo: &mut ShipCargo, tdelta: f64) -> bool {
+    pub fn update_cargo(
+        &self,
+        cargo: &mut ShipCargo,
+        extracted: &mut BTreeMap<Resource, f64>,
+        tdelta: f64,
+    ) -> bool {
         for (res, rate) in self.0.iter() {
-            cargo.add_resource(res, *rate * tdelta);
+            let added = cargo.add_resource(res, *rate * tdelta);
+            *extracted.entry(*res).or_default() += added;
         }
         cargo.is_full()
     }
@@ -5,4 +5,9 @@ pub struct ShipStats {
     pub speed: f64,
     pub fuel_consumption: f64,
     pub hull_usage_rate: f64,
+    // Hull repaired per second in flight, by the repair drones
+    pub repair_rate: f64,
+    // Capacities added by the modules
+    pub cargo_bonus: f64,
+    pub fuel_tank_bonus: f64,
 }
@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::galaxy::station::StationId;
+use crate::galaxy::{get_distance, SpaceCoord};
+
+// The tow service charges a fixed price, plus a price for each unit of distance
+const TOW_BASE_PRICE: f64 = 2000.0;
+const TOW_PRICE_PER_DIST: f64 = 25.0;
+// Time for the tow ship to reach the stranded ship, then it flies back at this speed
+const TOW_BASE_DELAY: f64 = 60.0;
+const TOW_SPEED: f64 = 15.0;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct TowInfo {
+    pub station: StationId,
+    pub destination: SpaceCoord,
+    pub price: f64,
+    // Game seconds until the ship is brought back to the station
+    pub remaining: f64,
+}
+
+impl TowInfo {
+    // The ship is brought back to the nearest station owned by the player
+    pub fn nearest_station(
+        position: &SpaceCoord,
+        stations: &BTreeMap<StationId, SpaceCoord>,
+        inflation: f64,
+    ) -> Option<TowInfo> {
+        let (station, coord) = stations.iter().min_by(|(_, a), (_, b)| {
+            get_distance(position, a).total_cmp(&get_distance(position, b))
+        })?;
+        let distance = get_distance(position, coord);
+        Some(TowInfo {
+            station: *station,
+            destination: *coord,
+            price: (TOW_BASE_PRICE + (distance * TOW_PRICE_PER_DIST)) * inflation,
+            remaining: TOW_BASE_DELAY + (distance / TOW_SPEED),
+        })
+    }
+}
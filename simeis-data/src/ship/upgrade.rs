@@ -34,13 +34,14 @@ pub enum ShipUpgrade {
 }
 
 impl ShipUpgrade {
-    pub fn get_price(&self) -> f64 {
-        match self {
+    pub fn get_price(&self, inflation: f64) -> f64 {
+        let price = match self {
             ShipUpgrade::CargoExpansion => CARGO_EXP_ADD_CAP * CARGO_CAP_PRICE,
             ShipUpgrade::ReactorUpgrade => (REACTOR_UPG_ADD as f64) * REACTOR_POWER_PRICE,
             ShipUpgrade::HullUpgrade => HULL_UPG_ADD * HULL_DECAY_CAP_PRICE,
             ShipUpgrade::Shield => (SHIELD_UPG_ADD as f64) * SHIELD_PRICE,
-        }
+        };
+        price * inflation
     }
 
     pub fn install(&self, ship: &mut Ship) {
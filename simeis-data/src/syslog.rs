@@ -1,12 +1,13 @@
 #![allow(clippy::type_complexity)]
 use std::collections::BTreeMap;
 use std::sync::Arc;
-use tokio::sync::mpsc::{error::TryRecvError, Receiver, Sender};
+use tokio::sync::mpsc::{error::TryRecvError, UnboundedReceiver, UnboundedSender};
 use tokio::sync::{Mutex, RwLock};
 
 use serde::{Deserialize, Serialize};
 use strum::IntoStaticStr;
 
+use crate::metrics::EventCounts;
 use crate::player::PlayerId;
 
 const SYSLOG_FIFO_MAX_SIZE: usize = 10;
@@ -67,15 +68,17 @@ impl<T> Fifo<T> {
     }
 }
 
+// The channel is unbounded so sending an event never waits on the game thread,
+// events can then be sent while holding locks on the game state
 #[derive(Clone)]
 pub struct SyslogSend {
-    sender: Sender<SyslogData>,
+    sender: UnboundedSender<SyslogData>,
     tstart: std::time::Instant,
 }
 
 impl SyslogSend {
     pub fn channel() -> (SyslogSend, SyslogRecv) {
-        let (sender, recv) = tokio::sync::mpsc::channel(100);
+        let (sender, recv) = tokio::sync::mpsc::unbounded_channel();
         let tstart = std::time::Instant::now();
         let syslogsend = SyslogSend { sender, tstart };
         (syslogsend, SyslogRecv::init(recv, tstart))
@@ -83,24 +86,26 @@ impl SyslogSend {
 
     pub async fn event(&self, player: &PlayerId, evt: SyslogEvent) {
         let ns = self.tstart.elapsed().as_secs_f64();
-        self.sender.send((*player, ns, evt)).await.unwrap();
+        self.sender.send((*player, ns, evt)).unwrap();
     }
 }
 
 pub type SyslogFifo = Arc<RwLock<BTreeMap<PlayerId, Arc<RwLock<Fifo<(f64, SyslogEvent)>>>>>>;
 
 pub struct SyslogRecv {
-    recv: Mutex<Receiver<SyslogData>>,
+    recv: Mutex<UnboundedReceiver<SyslogData>>,
     pub(crate) fifo: SyslogFifo,
+    pub(crate) counts: EventCounts,
     tstart: std::time::Instant,
 }
 
 impl SyslogRecv {
-    pub fn init(recv: Receiver<SyslogData>, tstart: std::time::Instant) -> SyslogRecv {
+    pub fn init(recv: UnboundedReceiver<SyslogData>, tstart: std::time::Instant) -> SyslogRecv {
         SyslogRecv {
             recv: Mutex::new(recv),
             tstart,
             fifo: Arc::new(RwLock::new(BTreeMap::new())),
+            counts: EventCounts::default(),
         }
     }
 
@@ -125,6 +130,8 @@ impl SyslogRecv {
 
     async fn add_to_fifo(&self, id: PlayerId, ns: f64, evt: SyslogEvent) {
         log::debug!("Player {id} got event {evt:?}");
+        let evtype: &'static str = (&evt).into();
+        *self.counts.lock().unwrap().entry(evtype).or_default() += 1;
         let ok = {
             let sysfifo = self.fifo.read().await; // OK
             if let Some(fifo) = sysfifo.get(&id) {
@@ -153,11 +160,62 @@ pub enum SyslogEvent {
     // General game events
     GameStarted,
     GameLost,
+    AchievementUnlocked {
+        achievement: crate::achievement::Achievement,
+        reward: f64,
+    },
 
     // Ship
     ShipDestroyed(crate::ship::ShipId),
-    ShipFlightFinished(crate::ship::ShipId),
-    ExtractionStopped(crate::ship::ShipId),
+    ShipArrived {
+        ship: crate::ship::ShipId,
+        position: crate::galaxy::SpaceCoord,
+        location: ArrivalLocation,
+    },
+    CargoFull {
+        ship: crate::ship::ShipId,
+        extracted: BTreeMap<crate::ship::resources::Resource, f64>,
+    },
+    RepairStopped(crate::ship::ShipId),
+    ShipOutOfFuel {
+        ship: crate::ship::ShipId,
+        position: crate::galaxy::SpaceCoord,
+    },
+    ShipTowed {
+        ship: crate::ship::ShipId,
+        station: crate::galaxy::station::StationId,
+    },
+    HullCritical {
+        ship: crate::ship::ShipId,
+        threshold: f64,
+        wear: f64,
+    },
+
+    // Crew
+    CrewHired {
+        station: crate::galaxy::station::StationId,
+        crew: crate::crew::CrewId,
+        member_type: crate::crew::CrewMemberType,
+        cost: f64,
+    },
+    CrewAssigned {
+        station: crate::galaxy::station::StationId,
+        crew: crate::crew::CrewId,
+        member_type: crate::crew::CrewMemberType,
+        ship: Option<crate::ship::ShipId>,
+        module: Option<crate::ship::module::ShipModuleId>,
+    },
+
+    // Market
+    MarketTrade {
+        station: crate::galaxy::station::StationId,
+        tx: crate::market::MarketTx,
+    },
+    PriceSpike {
+        resource: crate::ship::resources::Resource,
+        old_price: f64,
+        new_price: f64,
+    },
 
     // Warnings
     UnloadedNothing {
@@ -167,6 +225,13 @@ pub enum SyslogEvent {
     LowFunds(std::time::Duration),
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ArrivalLocation {
+    Station,
+    Planet,
+    EmptySpace,
+}
+
 #[test]
 fn test_syslog_fifo() {
     let mut fifo = Fifo::<usize>::new();
@@ -1,6 +1,13 @@
 #![allow(unexpected_cfgs)]
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 
+use crate::crew::{CrewId, CrewMember, CrewMemberType};
+use crate::galaxy::station::Station;
+use crate::galaxy::SpaceCoord;
+use crate::player::Player;
+use crate::ship::cargo::ShipCargo;
+use crate::ship::{Ship, ShipId};
+
 #[allow(unused_mut)]
 pub fn create_property_based_test<T: Fn(&mut SmallRng)>(mut niter: usize, reg: &[u64], f: T) {
     #[cfg(feature = "heavy_testing")]
@@ -21,3 +28,36 @@ pub fn create_property_based_test<T: Fn(&mut SmallRng)>(mut niter: usize, reg: &
         f(&mut rng);
     }
 }
+
+// A player owning a single station at the origin
+pub fn player_with_station(name: &str) -> (Player, Station) {
+    let player = Player::new(0, (0, (0, 0, 0)), name.to_string());
+    let station = Station::init(0, player.id, (0, 0, 0));
+    (player, station)
+}
+
+// A ship without modules nor crew, with fixed capacities and a full tank
+pub fn fixed_ship(id: ShipId, position: SpaceCoord) -> Ship {
+    let mut ship = Ship {
+        id,
+        position,
+        reactor_power: 2,
+        fuel_tank_capacity: 1000.0,
+        fuel_tank: 1000.0,
+        cargo: ShipCargo::with_capacity(200.0),
+        hull_decay_capacity: 3000.0,
+        module_slots: 3,
+        ..Default::default()
+    };
+    ship.update_perf_stats();
+    ship
+}
+
+// Puts a new crew member on board, as the pilot of the ship if it is one
+pub fn board_crew(ship: &mut Ship, id: CrewId, crewtype: CrewMemberType) {
+    if crewtype == CrewMemberType::Pilot {
+        ship.pilot = Some(id);
+    }
+    ship.crew.0.insert(id, CrewMember::from(crewtype));
+    ship.update_perf_stats();
+}
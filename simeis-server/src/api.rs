@@ -1,22 +1,26 @@
 use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Instant;
 
 use base64::{prelude::BASE64_STANDARD, Engine};
 use ntex::web::types::Path;
 use ntex::web::{self, HttpRequest, HttpResponse, ServiceConfig};
-use rand::Rng;
 use serde_json::{json, to_value, Value};
-use simeis_data::crew::{CrewId, CrewMember, CrewMemberType};
+use simeis_data::achievement::Achievement;
+use simeis_data::crew::{CrewId, CrewMemberType};
 use simeis_data::galaxy::station::StationId;
 use simeis_data::galaxy::SpaceUnit;
-use simeis_data::market::fee_rate;
+use simeis_data::game::{Game, GameSignal};
+use simeis_data::market::trader_fee_rate;
+use simeis_data::metrics::MetricsWriter;
 use simeis_data::player::{PlayerId, PlayerKey};
+use simeis_data::score::{leaderboard, LeaderboardEntry, Score};
 use simeis_data::ship::module::{ShipModuleId, ShipModuleType};
 use simeis_data::ship::resources::Resource;
 use simeis_data::ship::upgrade::ShipUpgrade;
-use simeis_data::ship::{Ship, ShipId};
+use simeis_data::ship::ShipId;
 use simeis_data::syslog::SyslogEvent;
 use strum::IntoEnumIterator;
 
@@ -24,6 +28,7 @@ pub type ApiResult = Result<Value, Errcode>;
 
 use simeis_data::errors::Errcode;
 
+use crate::metrics::RequestMetrics;
 use crate::GameState;
 
 // TO DO Use POST queries also, instead of everything with GET
@@ -33,16 +38,16 @@ use crate::GameState;
 macro_rules! get_player {
     ($srv:ident, $req:ident) => {{
         let Some(key) = get_player_key(&$req) else {
-            return build_response(Err(Errcode::NoPlayerKey));
+            return build_response(&$srv, Err(Errcode::NoPlayerKey));
         };
         let index = $srv.player_index.read().await;
         let Some(id) = index.get(&key) else {
-            return build_response(Err(Errcode::NoPlayerWithKey));
+            return build_response(&$srv, Err(Errcode::NoPlayerWithKey));
         };
         let players = $srv.players.read().await;
         let player = players.get(id).unwrap();
         if player.read().await.lost {
-            return build_response(Err(Errcode::PlayerLost));
+            return build_response(&$srv, Err(Errcode::PlayerLost));
         }
         player.clone()
     }};
@@ -52,7 +57,7 @@ macro_rules! get_station {
     ($srv:expr, $player:expr, $id:expr) => {{
         let player = $player.read().await;
         let Some(station_coord) = player.stations.get($id).cloned() else {
-            return build_response(Err(Errcode::NoSuchStation(*$id)));
+            return build_response(&$srv, Err(Errcode::NoSuchStation(*$id)));
         };
         drop(player);
         $srv.galaxy
@@ -65,7 +70,7 @@ macro_rules! get_station {
 
     ($srv:expr, $id:expr; $player:expr) => {{
         let Some(station_coord) = $player.stations.get($id).cloned() else {
-            return build_response(Err(Errcode::NoSuchStation(*$id)));
+            return build_response(&$srv, Err(Errcode::NoSuchStation(*$id)));
         };
         $srv.galaxy
             .read()
@@ -77,27 +82,14 @@ macro_rules! get_station {
 
     ($srv:expr, $id:expr; $player:expr; $galaxy:expr) => {{
         let Some(station_coord) = $player.stations.get($id).cloned() else {
-            return build_response(Err(Errcode::NoSuchStation(*$id)));
+            return build_response(&$srv, Err(Errcode::NoSuchStation(*$id)));
         };
         $galaxy.get_station(&station_coord).await.unwrap()
     }};
 }
 
-// TO DO    Ensure that multiple players cannot lock themselves:
-//     Ask for write on X, wait for read on Y
-//     Ask for write on Y, wait for read on X
-
-// TO DO    Centralise every read / write in the API
-//     and give them a specific order:
-//         player first, station after, galaxy then, etc...
-// - Player index
-// - Player list
-// - Player
-// - Galaxy
-// - Station
-// - Market
-// - SyslogFifo
-// - PlayerFifo
+// Every endpoint must take its locks following the hierarchy documented
+// on simeis_data::game::Game
 
 fn get_player_key(req: &HttpRequest) -> Option<PlayerKey> {
     for q in req.query_string().split("&") {
@@ -125,8 +117,8 @@ pub fn jsonmerge(a: &mut Value, b: &Value) {
     }
 }
 
-fn build_response(res: ApiResult) -> HttpResponse {
-    let body = match res {
+fn build_response(srv: &Game, res: ApiResult) -> HttpResponse {
+    let mut body = match res {
         Ok(mut data) => {
             jsonmerge(&mut data, &json!({"error": "ok"}));
             data
@@ -135,6 +127,9 @@ fn build_response(res: ApiResult) -> HttpResponse {
             json!({"error": e.errmsg(), "type": format!("{e:?}")})
         }
     };
+    if let Value::Object(ref mut body) = body {
+        body.insert("tick".to_string(), json!(srv.clock.tick()));
+    }
 
     HttpResponse::Ok()
         .content_type("application/json")
@@ -143,8 +138,8 @@ fn build_response(res: ApiResult) -> HttpResponse {
 
 // CHECKED
 #[web::get("/ping")]
-async fn ping() -> impl web::Responder {
-    build_response(Ok(json!({"ping": "pong"})))
+async fn ping(srv: GameState) -> impl web::Responder {
+    build_response(&srv, Ok(json!({"ping": "pong"})))
 }
 
 // CHECKED
@@ -154,7 +149,7 @@ async fn get_syslogs(srv: GameState, req: HttpRequest) -> impl web::Responder {
     let pid = player.read().await.id;
     let allfifo = srv.fifo_events.read().await;
     let Some(fifo) = allfifo.get(&pid) else {
-        return build_response(Ok(json!({"nb": 0, "events": []})));
+        return build_response(&srv, Ok(json!({"nb": 0, "events": []})));
     };
     let fifo = fifo.clone();
     drop(allfifo);
@@ -171,65 +166,68 @@ async fn get_syslogs(srv: GameState, req: HttpRequest) -> impl web::Responder {
             })
         })
         .collect::<Vec<Value>>();
-    build_response(Ok(json!({ "nb": res.len(), "events": res, })))
+    build_response(&srv, Ok(json!({ "nb": res.len(), "events": res, })))
 }
 
 // CHECKED
 #[web::get("/player/new/{name}")]
 async fn new_player(srv: GameState, name: Path<String>) -> impl web::Responder {
     let name = name.to_string();
-    let players = srv.players.read().await;
-    let all_players = players.keys().collect::<Vec<&PlayerId>>();
-    for pid in all_players {
-        let player = players.get(pid).unwrap();
-        if name == player.read().await.name {
-            return build_response(Err(Errcode::PlayerAlreadyExists(*pid, name)));
-        }
-    }
-    drop(players);
-
     let res = srv.new_player(name).await;
-    build_response(res.map(|(id, key)| {
-        json!({
-            "playerId": id,
-            "key": key,
-        })
-    }))
+    build_response(
+        &srv,
+        res.map(|(id, key)| {
+            json!({
+                "playerId": id,
+                "key": key,
+            })
+        }),
+    )
+}
+
+#[web::get("/player/achievements")]
+async fn get_player_achievements(srv: GameState, req: HttpRequest) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let player = player.read().await;
+    build_response(
+        &srv,
+        Ok(json!({
+            "unlocked": player.achievements,
+            "all": Achievement::list(srv.config.achievement_rewards),
+        })),
+    )
+}
+
+#[web::get("/player/scores")]
+async fn get_player_scores(srv: GameState, req: HttpRequest) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let player = player.read().await;
+    let scores: Vec<&Score> = player.scores.iter().collect();
+    build_response(&srv, Ok(json!({ "scores": scores })))
+}
+
+#[web::get("/player/map")]
+async fn get_player_map(srv: GameState, req: HttpRequest) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let player = player.read().await;
+    build_response(&srv, Ok(player.map.to_json()))
 }
 
 // CHECKED
 #[web::get("/player/{id}")]
 async fn get_player(srv: GameState, id: Path<PlayerId>, req: HttpRequest) -> impl web::Responder {
     let Some(key) = get_player_key(&req) else {
-        return build_response(Err(Errcode::NoPlayerKey));
+        return build_response(&srv, Err(Errcode::NoPlayerKey));
     };
     let id = id.as_ref();
 
     let players = srv.players.read().await;
     let Some(player) = players.get(id) else {
-        return build_response(Err(Errcode::PlayerNotFound(*id)));
+        return build_response(&srv, Err(Errcode::PlayerNotFound(*id)));
     };
     let player = player.read().await;
 
-    let res = if player.key == key {
-        Ok(json!({
-            "id": id,
-            "name": player.name,
-            "stations": player.stations,
-            "money": player.money,
-            "ships": serde_json::to_value(
-                player.ships.values().collect::<Vec<&Ship>>()
-            ).unwrap(),
-            "costs": player.costs,
-        }))
-    } else {
-        Ok(json!({
-            "id": id,
-            "name": player.name,
-            "stations": player.stations,
-        }))
-    };
-    build_response(res)
+    build_response(&srv, Ok(player.to_json(player.key == key)))
 }
 
 // CHECKED
@@ -243,14 +241,33 @@ async fn get_station_status(
     let station = get_station!(srv, player, id.as_ref());
     let station = station.read().await;
 
-    build_response(Ok(json!({
-        "id": station.id,
-        "position": station.position,
-        "crew": station.crew,
-        "cargo": station.cargo,
-        "idle_crew": station.idle_crew,
-        "trader": station.trader,
-    })))
+    let market = srv.market.read().await;
+    let resource_prices: BTreeMap<Resource, f64> = market
+        .prices
+        .iter()
+        .map(|(r, p)| (*r, p * station.economy.resource_rate(r)))
+        .collect();
+    drop(market);
+
+    build_response(
+        &srv,
+        Ok(json!({
+            "id": station.id,
+            "position": station.position,
+            "crew": station.crew,
+            "cargo": station.cargo,
+            "idle_crew": station.idle_crew,
+            "trader": station.trader,
+            "scanner_rank": station.scanner_rank,
+            "economy": {
+                "price-rates": station.economy.price_rates(),
+                "resource-prices": resource_prices,
+                "demand": station.economy.demand,
+                "recent-purchases": station.economy.recent_purchases,
+                "shipyard-quality": station.offers_quality(),
+            },
+        })),
+    )
 }
 
 // CHECKED
@@ -268,15 +285,18 @@ async fn list_shipyard_ships(
     for ship in station.shipyard.iter() {
         ships.push(json!({
             "id": ship.id,
+            "hull_class": ship.hull_class,
+            "rarity": ship.rarity,
             "modules": ship.modules,
             "reactor_power": ship.reactor_power,
             "cargo_capacity": ship.cargo.capacity,
             "fuel_tank_capacity": ship.fuel_tank_capacity,
             "hull_decay_capacity": ship.hull_decay_capacity,
-            "price": ship.compute_price(),
+            "shield_power": ship.shield_power,
+            "price": station.ship_price(ship, srv.inflation()),
         }));
     }
-    build_response(Ok(json!({ "ships": ships })))
+    build_response(&srv, Ok(json!({ "ships": ships })))
 }
 
 // CHECKED
@@ -295,12 +315,53 @@ async fn shipyard_buy_ship(
     let mut station = station.write().await;
 
     build_response(
+        &srv,
         player
-            .buy_ship(&mut station, *ship_id)
+            .buy_ship(&mut station, *ship_id, srv.inflation())
             .map(|v| json!({ "shipId": v, })),
     )
 }
 
+#[web::get("/station/{station_id}/shipyard/sell/{ship_id}")]
+async fn shipyard_sell_ship(
+    srv: GameState,
+    args: Path<(StationId, ShipId)>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (station_id, ship_id) = args.as_ref();
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+    let galaxy = srv.galaxy.read().await;
+    let station = get_station!(srv, station_id; player; galaxy);
+    let mut station = station.write().await;
+
+    let res = player.sell_ship(&mut station, ship_id, srv.inflation());
+    drop(station);
+    player.update_wages(&galaxy).await;
+    build_response(&srv, res.map(|v| json!({ "price": v })))
+}
+
+#[web::get("/station/{station_id}/shipyard/scrap/{ship_id}")]
+async fn shipyard_scrap_ship(
+    srv: GameState,
+    args: Path<(StationId, ShipId)>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (station_id, ship_id) = args.as_ref();
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+    let galaxy = srv.galaxy.read().await;
+    let station = get_station!(srv, station_id; player; galaxy);
+    let mut station = station.write().await;
+
+    let res = player.scrap_ship(&mut station, ship_id);
+    drop(station);
+    player.update_wages(&galaxy).await;
+    build_response(&srv, res.map(|v| json!({ "recovered": v })))
+}
+
 // CHECKED
 // TO DO IMPORTANT    Get ship ID here, and adapt prices based on the ranks of the modules
 #[web::get("/station/{station_id}/shipyard/upgrade")]
@@ -318,12 +379,12 @@ async fn shipyard_list_upgrades(
         res.insert(
             upgr,
             json!({
-                "price": station.get_ship_upgrade_price(&upgr),
+                "price": station.get_ship_upgrade_price(&upgr, srv.inflation()),
                 "description": upgr.description(),
             }),
         );
     }
-    build_response(Ok(to_value(res).unwrap()))
+    build_response(&srv, Ok(to_value(res).unwrap()))
 }
 
 // CHECKED
@@ -335,7 +396,7 @@ async fn shipyard_buy_upgrade(
 ) -> impl web::Responder {
     let (station_id, ship_id, upgrade_type) = args.as_ref();
     let Ok(upgrade_type) = ShipUpgrade::from_str(upgrade_type) else {
-        return build_response(Err(Errcode::InvalidArgument("upgrade type")));
+        return build_response(&srv, Err(Errcode::InvalidArgument("upgrade type")));
     };
     let player = get_player!(srv, req);
     let mut player = player.write().await;
@@ -344,8 +405,9 @@ async fn shipyard_buy_upgrade(
     let mut station = station.write().await;
 
     build_response(
+        &srv,
         player
-            .buy_ship_upgrade(&mut station, ship_id, &upgrade_type)
+            .buy_ship_upgrade(&mut station, ship_id, &upgrade_type, srv.inflation())
             .map(|v| json!({ "cost": v })),
     )
 }
@@ -359,7 +421,7 @@ async fn hire_crew(
 ) -> impl web::Responder {
     let (station_id, crewtype) = args.as_ref();
     let Ok(crewtype) = CrewMemberType::from_str(crewtype.as_str()) else {
-        return build_response(Err(Errcode::InvalidArgument("crewtype")));
+        return build_response(&srv, Err(Errcode::InvalidArgument("crewtype")));
     };
 
     let player = get_player!(srv, req);
@@ -368,13 +430,78 @@ async fn hire_crew(
     let station = get_station!(srv, station_id; player; galaxy);
     let mut station = station.write().await;
 
-    let mut rng = rand::rng();
-    let id = rng.random();
-    let member = CrewMember::from(crewtype);
-    station.idle_crew.0.insert(id, member);
+    let res = player.hire_crew(&mut station, crewtype.clone(), srv.inflation());
+    drop(station);
+    player.update_wages(&galaxy).await;
+    if let Ok((crew, cost)) = res {
+        let event = SyslogEvent::CrewHired {
+            station: *station_id,
+            crew,
+            member_type: crewtype,
+            cost,
+        };
+        srv.syslog.event(&player.id, event).await;
+    }
+    build_response(
+        &srv,
+        res.map(|(id, cost)| json!({ "id": id, "cost": cost })),
+    )
+}
+
+#[web::get("/station/{station_id}/crew/candidates")]
+async fn list_crew_candidates(
+    srv: GameState,
+    station_id: Path<StationId>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let station = get_station!(srv, player, station_id.as_ref());
+    let station = station.read().await;
+
+    let inflation = srv.inflation();
+    let candidates: Vec<Value> = station
+        .candidates
+        .iter()
+        .map(|c| c.to_json(inflation))
+        .collect();
+    build_response(&srv, Ok(json!({ "candidates": candidates })))
+}
+
+#[web::get("/station/{station_id}/crew/candidates/hire/{crew_id}")]
+async fn hire_crew_candidate(
+    srv: GameState,
+    args: Path<(StationId, CrewId)>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (station_id, crew_id) = args.as_ref();
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+    let galaxy = srv.galaxy.read().await;
+    let station = get_station!(srv, station_id; player; galaxy);
+    let mut station = station.write().await;
+
+    let res = player.hire_candidate(&mut station, *crew_id, srv.inflation());
+    let member_type = res
+        .as_ref()
+        .ok()
+        .and_then(|(id, _)| station.idle_crew.0.get(id))
+        .map(|cm| cm.member_type.clone());
     drop(station);
     player.update_wages(&galaxy).await;
-    build_response(Ok(serde_json::json!({ "id": id })))
+    if let (Ok((crew, cost)), Some(member_type)) = (&res, member_type) {
+        let event = SyslogEvent::CrewHired {
+            station: *station_id,
+            crew: *crew,
+            member_type,
+            cost: *cost,
+        };
+        srv.syslog.event(&player.id, event).await;
+    }
+    build_response(
+        &srv,
+        res.map(|(id, cost)| json!({ "id": id, "cost": cost })),
+    )
 }
 
 // CHECKED
@@ -390,13 +517,13 @@ async fn get_crew_upgrades(
     let player = player.read().await;
 
     let Some(ship) = player.ships.get(ship_id) else {
-        return build_response(Err(Errcode::ShipNotFound(*ship_id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*ship_id)));
     };
 
     let station = get_station!(srv, station_id; player);
     let station = station.read().await;
     if ship.position != station.position {
-        return build_response(Err(Errcode::ShipNotInStation));
+        return build_response(&srv, Err(Errcode::ShipNotInStation));
     }
 
     let mut res = BTreeMap::new();
@@ -406,11 +533,12 @@ async fn get_crew_upgrades(
             json!({
                 "member-type": cm.member_type,
                 "rank": cm.rank + 1,
-                "price": cm.price_next_rank(),
+                "max-rank": cm.member_type.max_rank(),
+                "price": cm.price_next_rank(srv.inflation()),
             }),
         );
     }
-    build_response(Ok(to_value(res).unwrap()))
+    build_response(&srv, Ok(to_value(res).unwrap()))
 }
 
 // CHECKED
@@ -428,12 +556,12 @@ async fn buy_crew_upgrade(
     let station = get_station!(srv, station_id; player; galaxy);
     let station = station.read().await;
 
-    let res = player.upgrade_crew_rank(&station, ship_id, crew_id);
+    let res = player.upgrade_crew_rank(&station, ship_id, crew_id, srv.inflation());
     if res.is_ok() {
         drop(station);
         player.update_wages(&galaxy).await;
     }
-    build_response(res.map(|(p, r)| json!({ "new-rank": r, "cost": p})))
+    build_response(&srv, res.map(|(p, r)| json!({ "new-rank": r, "cost": p})))
 }
 
 // CHECKED
@@ -450,12 +578,12 @@ async fn upgrade_station_trader(
     let station = get_station!(srv, station_id.as_ref(); player; galaxy);
     let mut station = station.write().await;
 
-    let res = player.upgrade_station_trader(station.deref_mut());
+    let res = player.upgrade_station_trader(station.deref_mut(), srv.inflation());
     if res.is_ok() {
         drop(station);
         player.update_wages(&galaxy).await;
     }
-    build_response(res.map(|(p, r)| json!({ "new-rank": r, "cost": p })))
+    build_response(&srv, res.map(|(p, r)| json!({ "new-rank": r, "cost": p })))
 }
 
 // CHECKED
@@ -471,7 +599,18 @@ async fn assign_trader(
     let station = get_station!(srv, player, station_id);
     let mut station = station.write().await;
 
-    build_response(station.assign_trader(*crew_id).map(|_| json!({})))
+    let res = station.assign_trader(*crew_id);
+    if res.is_ok() {
+        let event = SyslogEvent::CrewAssigned {
+            station: *station_id,
+            crew: *crew_id,
+            member_type: CrewMemberType::Trader,
+            ship: None,
+            module: None,
+        };
+        srv.syslog.event(&station.owner, event).await;
+    }
+    build_response(&srv, res.map(|_| json!({})))
 }
 
 // CHECKED
@@ -490,9 +629,20 @@ async fn assign_pilot(
     let mut station = station.write().await;
 
     let Some(ship) = player.ships.get_mut(ship_id) else {
-        return build_response(Err(Errcode::ShipNotFound(*ship_id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*ship_id)));
     };
-    build_response(station.onboard_pilot(*crew_id, ship).map(|_| json!({})))
+    let res = station.onboard_pilot(*crew_id, ship);
+    if res.is_ok() {
+        let event = SyslogEvent::CrewAssigned {
+            station: *station_id,
+            crew: *crew_id,
+            member_type: CrewMemberType::Pilot,
+            ship: Some(*ship_id),
+            module: None,
+        };
+        srv.syslog.event(&player.id, event).await;
+    }
+    build_response(&srv, res.map(|_| json!({})))
 }
 
 // CHECKED
@@ -511,28 +661,128 @@ async fn assign_operator(
     let mut station = station.write().await;
 
     let Some(ship) = player.ships.get_mut(ship_id) else {
-        return build_response(Err(Errcode::ShipNotFound(*ship_id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*ship_id)));
     };
+    let res = station.onboard_operator(*crew_id, ship, modid);
+    if res.is_ok() {
+        let event = SyslogEvent::CrewAssigned {
+            station: *station_id,
+            crew: *crew_id,
+            member_type: CrewMemberType::Operator,
+            ship: Some(*ship_id),
+            module: Some(*modid),
+        };
+        srv.syslog.event(&player.id, event).await;
+    }
+    build_response(&srv, res.map(|_| json!({})))
+}
+
+#[web::get("/station/{station_id}/crew/unassign/trading")]
+async fn unassign_trader(
+    station_id: Path<StationId>,
+    srv: GameState,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let station = get_station!(srv, player, station_id.as_ref());
+    let mut station = station.write().await;
+
     build_response(
-        station
-            .onboard_operator(*crew_id, ship, modid)
-            .map(|_| json!({})),
+        &srv,
+        station.unassign_trader().map(|id| json!({ "id": id })),
+    )
+}
+
+#[web::get("/station/{station_id}/crew/unassign/{crewid}/{shipid}")]
+async fn unassign_crew(
+    args: Path<(StationId, CrewId, ShipId)>,
+    srv: GameState,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (station_id, crew_id, ship_id) = args.as_ref();
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let station = get_station!(srv, station_id; player);
+    let mut station = station.write().await;
+
+    let Some(ship) = player.ships.get_mut(ship_id) else {
+        return build_response(&srv, Err(Errcode::ShipNotFound(*ship_id)));
+    };
+    build_response(
+        &srv,
+        station.unboard_crew(*crew_id, ship).map(|_| json!({})),
     )
 }
 
+#[web::get("/station/{station_id}/crew/transfer/{crewid}/{from}/{to}")]
+async fn transfer_crew(
+    args: Path<(StationId, CrewId, ShipId, ShipId)>,
+    srv: GameState,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (station_id, crew_id, from, to) = args.as_ref();
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let station = get_station!(srv, station_id; player);
+    let station = station.read().await;
+
+    let res = player.transfer_crew(&station, *crew_id, from, to);
+    if let Ok(module) = res {
+        let member_type = match module {
+            None => CrewMemberType::Pilot,
+            Some(_) => CrewMemberType::Operator,
+        };
+        let event = SyslogEvent::CrewAssigned {
+            station: *station_id,
+            crew: *crew_id,
+            member_type,
+            ship: Some(*to),
+            module,
+        };
+        srv.syslog.event(&player.id, event).await;
+    }
+    build_response(&srv, res.map(|modid| json!({ "module": modid })))
+}
+
+#[web::get("/station/{station_id}/crew/fire/{crewid}")]
+async fn fire_crew(
+    args: Path<(StationId, CrewId)>,
+    srv: GameState,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (station_id, crew_id) = args.as_ref();
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+    let galaxy = srv.galaxy.read().await;
+    let station = get_station!(srv, station_id; player; galaxy);
+    let mut station = station.write().await;
+
+    let res = player.fire_crew(&mut station, *crew_id, srv.inflation());
+    drop(station);
+    player.update_wages(&galaxy).await;
+    build_response(&srv, res.map(|v| json!({ "severance": v })))
+}
+
 // CHECKED
 #[web::get("/station/{station_id}/scan")]
 async fn scan(id: Path<StationId>, srv: GameState, req: HttpRequest) -> impl web::Responder {
     let player = get_player!(srv, req);
-    let player = player.read().await;
+    let ships = srv.ships_in_flight().await;
+    let mut player = player.write().await;
 
     let galaxy = srv.galaxy.read().await;
 
     let station = get_station!(srv, id.as_ref(); player; galaxy);
     let station = station.read().await;
 
-    let results = station.scan(&galaxy).await;
-    build_response(Ok(to_value(&results).unwrap()))
+    let results = station.scan(&galaxy, &ships).await;
+    player.learn(&results);
+    build_response(&srv, Ok(to_value(&results).unwrap()))
 }
 
 // CHECKED
@@ -543,16 +793,16 @@ async fn get_prices_ship_module(
     req: HttpRequest,
 ) -> impl web::Responder {
     let player = get_player!(srv, req);
-    let _station = get_station!(srv, player, id.as_ref()); // Ensure it exists
+    let station = get_station!(srv, player, id.as_ref());
+    let station = station.read().await;
 
-    // TO DO (#22) Price based on station
     let mut res: BTreeMap<ShipModuleType, f64> = BTreeMap::new();
     for smod in ShipModuleType::iter() {
-        let price = smod.get_price_buy();
+        let price = station.module_price(&smod, srv.inflation());
         res.insert(smod, price);
     }
 
-    build_response(Ok(to_value(res).unwrap()))
+    build_response(&srv, Ok(to_value(res).unwrap()))
 }
 
 // CHECKED
@@ -564,15 +814,19 @@ async fn buy_ship_module(
 ) -> impl web::Responder {
     let (station_id, ship_id, modtype) = args.as_ref();
     let Ok(modtype) = ShipModuleType::from_str(modtype.as_str()) else {
-        return build_response(Err(Errcode::InvalidArgument("modtype")));
+        return build_response(&srv, Err(Errcode::InvalidArgument("modtype")));
     };
 
     let player = get_player!(srv, req);
     let mut player = player.write().await;
 
+    let station = get_station!(srv, station_id; player);
+    let mut station = station.write().await;
+
     build_response(
+        &srv,
         player
-            .buy_ship_module(station_id, ship_id, modtype)
+            .buy_ship_module(&mut station, ship_id, modtype, srv.inflation())
             .map(|v| {
                 json!({
                     "id": v,
@@ -581,6 +835,28 @@ async fn buy_ship_module(
     )
 }
 
+#[web::get("/station/{station_id}/shop/modules/{ship_id}/sell/{mod_id}")]
+async fn sell_ship_module(
+    srv: GameState,
+    args: Path<(StationId, ShipId, ShipModuleId)>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (station_id, ship_id, mod_id) = args.as_ref();
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let station = get_station!(srv, station_id; player);
+    let mut station = station.write().await;
+
+    build_response(
+        &srv,
+        player
+            .sell_ship_module(&mut station, ship_id, mod_id, srv.inflation())
+            .map(|price| json!({ "price": price })),
+    )
+}
+
 // CHECKED
 #[web::get("/station/{station_id}/shop/modules/{ship_id}/upgrade")]
 async fn get_ship_module_upgrade_prices(
@@ -594,14 +870,14 @@ async fn get_ship_module_upgrade_prices(
     let player = player.read().await;
 
     let Some(ship) = player.ships.get(ship_id) else {
-        return build_response(Err(Errcode::ShipNotFound(*ship_id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*ship_id)));
     };
 
     let station = get_station!(srv, station_id; player);
     let station = station.read().await;
 
     if ship.position != station.position {
-        return build_response(Err(Errcode::ShipNotInStation));
+        return build_response(&srv, Err(Errcode::ShipNotInStation));
     }
 
     let mut res = BTreeMap::new();
@@ -610,11 +886,13 @@ async fn get_ship_module_upgrade_prices(
             id,
             json!({
                 "module-type": smod.modtype,
-                "price": smod.price_next_rank(),
+                "rank": smod.rank + 1,
+                "max-rank": smod.modtype.max_rank(),
+                "price": station.module_upgrade_price(smod, srv.inflation()),
             }),
         );
     }
-    build_response(Ok(to_value(res).unwrap()))
+    build_response(&srv, Ok(to_value(res).unwrap()))
 }
 
 // CHECKED
@@ -630,11 +908,12 @@ async fn buy_ship_module_upgrade(
     let mut player = player.write().await;
 
     let station = get_station!(srv, station_id; player);
-    let station = station.read().await;
+    let mut station = station.write().await;
 
     build_response(
+        &srv,
         player
-            .buy_ship_module_upgrade(&station, ship_id, mod_id)
+            .buy_ship_module_upgrade(&mut station, ship_id, mod_id, srv.inflation())
             .map(|(c, r)| {
                 json!({
                     "new-rank": r,
@@ -660,8 +939,9 @@ async fn buy_station_cargo(
     let mut station = station.write().await;
 
     build_response(
+        &srv,
         station
-            .buy_cargo(player.deref_mut(), amnt)
+            .buy_cargo(player.deref_mut(), amnt, srv.inflation())
             .map(|v| to_value(v).unwrap()),
     )
 }
@@ -677,16 +957,40 @@ async fn get_station_upgrades(
     let station = get_station!(srv, player, id.as_ref());
     let station = station.read().await;
 
-    let cargoprice = station.cargo_price();
+    let cargoprice = station.cargo_price(srv.inflation());
     let traderprice = station.trader.map(|trader| {
         let cm = station.crew.0.get(&trader).unwrap();
-        cm.price_next_rank()
+        cm.price_next_rank(srv.inflation())
     });
 
-    build_response(Ok(json!({
-        "cargo-expansion": cargoprice,
-        "trader-upgrade": traderprice,
-    })))
+    build_response(
+        &srv,
+        Ok(json!({
+            "cargo-expansion": cargoprice,
+            "trader-upgrade": traderprice,
+            "scanner-upgrade": station.scanner_price(srv.inflation()),
+        })),
+    )
+}
+
+#[web::get("/station/{station_id}/shop/scanner/upgrade")]
+async fn buy_station_scanner_upgrade(
+    srv: GameState,
+    id: Path<StationId>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let station = get_station!(srv, id.as_ref(); player);
+    let mut station = station.write().await;
+
+    build_response(
+        &srv,
+        station
+            .buy_scanner_upgrade(player.deref_mut(), srv.inflation())
+            .map(|(c, r)| json!({ "new-rank": r, "cost": c })),
+    )
 }
 
 // CHECKED
@@ -705,11 +1009,11 @@ async fn refuel_ship(
     let mut station = station.write().await;
 
     let Some(ship) = player.ships.get_mut(ship_id) else {
-        return build_response(Err(Errcode::ShipNotFound(*ship_id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*ship_id)));
     };
 
     let res = station.refuel_ship(ship).map(|v| json!({"added-fuel": v}));
-    build_response(res)
+    build_response(&srv, res)
 }
 
 // CHECKED
@@ -727,14 +1031,13 @@ async fn repair_ship(
     let mut station = station.write().await;
 
     let Some(ship) = player.ships.get_mut(ship_id) else {
-        return build_response(Err(Errcode::ShipNotFound(*ship_id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*ship_id)));
     };
 
     let res = station.repair_ship(ship).map(|v| json!({"added-hull": v}));
-    build_response(res)
+    build_response(&srv, res)
 }
 
-// FIXME Sometimes under heavy load, sometimes get a "Ship not found"
 // CHECKED
 #[web::get("/ship/{ship_id}")]
 async fn get_ship_status(
@@ -746,9 +1049,9 @@ async fn get_ship_status(
     let player = player.read().await;
 
     let Some(ship) = player.ships.get(id.as_ref()) else {
-        return build_response(Err(Errcode::ShipNotFound(*id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
     };
-    build_response(Ok(to_value(ship).unwrap()))
+    build_response(&srv, Ok(to_value(ship).unwrap()))
 }
 
 // CHECKED
@@ -764,10 +1067,11 @@ async fn compute_travel_costs(
     let player = player.read().await;
 
     let Some(ship) = player.ships.get(id) else {
-        return build_response(Err(Errcode::ShipNotFound(*id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
     };
 
     build_response(
+        &srv,
         ship.compute_travel_costs((*x, *y, *z))
             .map(|v| to_value(v).unwrap()),
     )
@@ -787,10 +1091,10 @@ async fn ask_navigate(
     let mut player = player.write().await;
 
     let Some(ship) = player.ships.get_mut(id) else {
-        return build_response(Err(Errcode::ShipNotFound(*id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
     };
 
-    build_response(ship.set_travel(coord).map(|cost| json!(cost)))
+    build_response(&srv, ship.set_travel(coord).map(|cost| json!(cost)))
 }
 
 // CHECKED
@@ -806,9 +1110,30 @@ async fn stop_navigation(
     let mut player = player.write().await;
 
     let Some(ship) = player.ships.get_mut(id) else {
-        return build_response(Err(Errcode::ShipNotFound(*id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
     };
-    build_response(ship.stop_navigation().map(|pos| json!({"position": pos})))
+    build_response(
+        &srv,
+        ship.stop_navigation().map(|pos| json!({"position": pos})),
+    )
+}
+
+#[web::get("/ship/{ship_id}/scan")]
+async fn ship_scan(srv: GameState, id: Path<ShipId>, req: HttpRequest) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let ships = srv.ships_in_flight().await;
+    let mut player = player.write().await;
+
+    let Some(ship) = player.ships.get(id.as_ref()) else {
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
+    };
+
+    let mut galaxy = srv.galaxy.write().await;
+    let res = ship.scan(&mut galaxy, &ships).await;
+    if let Ok(ref results) = res {
+        player.learn(results);
+    }
+    build_response(&srv, res.map(|results| to_value(&results).unwrap()))
 }
 
 // CHECKED
@@ -821,14 +1146,17 @@ async fn start_extraction(
     let player = get_player!(srv, req);
     let mut player = player.write().await;
     let Some(ship) = player.ships.get_mut(id.as_ref()) else {
-        return build_response(Err(Errcode::ShipNotFound(*id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
     };
     let galaxy = srv.galaxy.read().await;
-    build_response(
-        ship.start_extraction(&galaxy)
-            .await
-            .map(|v| to_value(v).unwrap()),
-    )
+    let res = ship.start_extraction(&galaxy).await;
+    if res
+        .as_ref()
+        .is_ok_and(|extraction| !extraction.0.is_empty())
+    {
+        player.unlock(Achievement::FirstExtraction);
+    }
+    build_response(&srv, res.map(|v| to_value(v).unwrap()))
 }
 
 // CHECKED
@@ -842,10 +1170,77 @@ async fn stop_extraction(
     let mut player = player.write().await;
 
     let Some(ship) = player.ships.get_mut(id.as_ref()) else {
-        return build_response(Err(Errcode::ShipNotFound(*id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
     };
 
-    build_response(ship.stop_extraction().map(|v| to_value(v).unwrap()))
+    build_response(&srv, ship.stop_extraction().map(|v| to_value(v).unwrap()))
+}
+
+#[web::get("/ship/{ship_id}/refuel")]
+async fn refuel_ship_from_cargo(
+    srv: GameState,
+    id: Path<ShipId>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let Some(ship) = player.ships.get_mut(id.as_ref()) else {
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
+    };
+
+    let res = ship.refuel_from_cargo().map(|v| json!({"added-fuel": v}));
+    build_response(&srv, res)
+}
+
+#[web::get("/ship/{ship_id}/repair/start")]
+async fn start_ship_repair(
+    srv: GameState,
+    id: Path<ShipId>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let Some(ship) = player.ships.get_mut(id.as_ref()) else {
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
+    };
+
+    build_response(&srv, ship.start_repair().map(|v| to_value(v).unwrap()))
+}
+
+#[web::get("/ship/{ship_id}/repair/stop")]
+async fn stop_ship_repair(
+    srv: GameState,
+    id: Path<ShipId>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let Some(ship) = player.ships.get_mut(id.as_ref()) else {
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
+    };
+
+    build_response(&srv, ship.stop_repair().map(|v| to_value(v).unwrap()))
+}
+
+#[web::get("/ship/{ship_id}/tow/cost")]
+async fn get_tow_cost(srv: GameState, id: Path<ShipId>, req: HttpRequest) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let player = player.read().await;
+
+    let res = player.tow_offer(id.as_ref(), srv.inflation());
+    build_response(&srv, res.map(|v| to_value(v).unwrap()))
+}
+
+#[web::get("/ship/{ship_id}/tow")]
+async fn call_tow(srv: GameState, id: Path<ShipId>, req: HttpRequest) -> impl web::Responder {
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let res = player.call_tow(id.as_ref(), srv.inflation());
+    build_response(&srv, res.map(|v| to_value(v).unwrap()))
 }
 
 // CHECKED
@@ -858,18 +1253,18 @@ async fn unload_ship_cargo(
     let (id, resource, amnt) = args.as_ref();
 
     let Ok(resource) = Resource::from_str(resource) else {
-        return build_response(Err(Errcode::InvalidArgument("resource")));
+        return build_response(&srv, Err(Errcode::InvalidArgument("resource")));
     };
 
     let player = get_player!(srv, req);
     let mut player = player.write().await;
 
     let Some(ship) = player.ships.get(id) else {
-        return build_response(Err(Errcode::ShipNotFound(*id)));
+        return build_response(&srv, Err(Errcode::ShipNotFound(*id)));
     };
 
     let Some(station) = player.stations.iter().find(|(_, s)| *s == &ship.position) else {
-        return build_response(Err(Errcode::ShipNotInStation));
+        return build_response(&srv, Err(Errcode::ShipNotInStation));
     };
 
     let station = get_station!(srv, station.0; player);
@@ -890,7 +1285,52 @@ async fn unload_ship_cargo(
             )
             .await;
     }
-    build_response(res.map(|v| json!({ "unloaded": v })))
+    build_response(&srv, res.map(|v| json!({ "unloaded": v })))
+}
+
+#[web::get("/ship/{ship_id}/transfer/{to_id}/fuel/{amount}")]
+async fn transfer_ship_fuel(
+    srv: GameState,
+    args: Path<(ShipId, ShipId, f64)>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (from, to, amnt) = args.as_ref();
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let res = player.transfer_fuel(from, to, *amnt);
+    build_response(&srv, res.map(|v| json!({ "transferred": v })))
+}
+
+#[web::get("/ship/{ship_id}/transfer/{to_id}/cargo/{resource}/{amount}")]
+async fn transfer_ship_cargo(
+    srv: GameState,
+    args: Path<(ShipId, ShipId, String, f64)>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    let (from, to, resource, amnt) = args.as_ref();
+
+    let Ok(resource) = Resource::from_str(resource) else {
+        return build_response(&srv, Err(Errcode::InvalidArgument("resource")));
+    };
+
+    let player = get_player!(srv, req);
+    let mut player = player.write().await;
+
+    let res = player.transfer_cargo(from, to, &resource, *amnt);
+    build_response(&srv, res.map(|v| json!({ "transferred": v })))
+}
+
+#[web::get("/market/inflation")]
+async fn get_inflation(srv: GameState) -> impl web::Responder {
+    build_response(
+        &srv,
+        Ok(json!({
+            "inflation": srv.inflation(),
+            "curve": srv.config.inflation,
+        })),
+    )
 }
 
 // CHECKED
@@ -898,7 +1338,7 @@ async fn unload_ship_cargo(
 async fn get_market_prices(srv: GameState) -> impl web::Responder {
     let market = srv.market.read().await;
     let res = to_value(market.deref()).unwrap();
-    build_response(Ok(res))
+    build_response(&srv, Ok(res))
 }
 
 // CHECKED
@@ -910,7 +1350,7 @@ async fn buy_resource(
 ) -> impl web::Responder {
     let (station_id, resource, amnt) = args.as_ref();
     let Ok(resource) = Resource::from_str(resource) else {
-        return build_response(Err(Errcode::InvalidArgument("resource")));
+        return build_response(&srv, Err(Errcode::InvalidArgument("resource")));
     };
 
     let player = get_player!(srv, req);
@@ -920,11 +1360,15 @@ async fn buy_resource(
     let mut station = station.write().await;
 
     let mut market = srv.market.write().await;
-    build_response(
-        station
-            .buy_resource(&resource, *amnt, player.deref_mut(), market.deref_mut())
-            .map(|tx| to_value(tx).unwrap()),
-    )
+    let res = station.buy_resource(&resource, *amnt, player.deref_mut(), market.deref_mut());
+    if let Ok(ref tx) = res {
+        let event = SyslogEvent::MarketTrade {
+            station: *station_id,
+            tx: tx.clone(),
+        };
+        srv.syslog.event(&player.id, event).await;
+    }
+    build_response(&srv, res.map(|tx| to_value(tx).unwrap()))
 }
 
 // CHECKED
@@ -936,7 +1380,7 @@ async fn sell_resource(
 ) -> impl web::Responder {
     let (station_id, resource, amnt) = args.as_ref();
     let Ok(resource) = Resource::from_str(resource) else {
-        return build_response(Err(Errcode::InvalidArgument("resource")));
+        return build_response(&srv, Err(Errcode::InvalidArgument("resource")));
     };
 
     let player = get_player!(srv, req);
@@ -946,10 +1390,15 @@ async fn sell_resource(
     let mut station = station.write().await;
 
     let mut market = srv.market.write().await;
-    let res = station
-        .sell_resource(&resource, *amnt, player.deref_mut(), market.deref_mut())
-        .map(|tx| to_value(tx).unwrap());
-    build_response(res)
+    let res = station.sell_resource(&resource, *amnt, player.deref_mut(), market.deref_mut());
+    if let Ok(ref tx) = res {
+        let event = SyslogEvent::MarketTrade {
+            station: *station_id,
+            tx: tx.clone(),
+        };
+        srv.syslog.event(&player.id, event).await;
+    }
+    build_response(&srv, res.map(|tx| to_value(tx).unwrap()))
 }
 
 // CHECKED
@@ -964,42 +1413,203 @@ async fn get_fee_rate(
     let station = station.read().await;
 
     let Some(trader) = station.trader else {
-        return build_response(Err(Errcode::NoTraderAssigned));
+        return build_response(&srv, Err(Errcode::NoTraderAssigned));
     };
 
     let cm = station.crew.0.get(&trader).unwrap();
-    let fee = fee_rate(cm.rank);
+    let fee = trader_fee_rate(cm);
 
-    build_response(Ok(json!({
-        "fee_rate": fee,
-    })))
+    build_response(
+        &srv,
+        Ok(json!({
+            "fee_rate": fee,
+        })),
+    )
 }
 
+macro_rules! check_admin {
+    ($srv:ident, $req:ident) => {{
+        if !is_admin(&$srv, &$req) {
+            return build_response(&$srv, Err(Errcode::NotAdmin));
+        }
+    }};
+}
+
+// Ticks sent at most by a single request, so it can't hold the game thread for too long
+const MAX_TICKS_PER_REQUEST: usize = 1000;
+
 // CHECKED
-#[cfg(feature = "testing")]
 #[web::get("/tick")]
-async fn tick_server(srv: GameState) -> impl web::Responder {
-    let Ok(_) = srv.send_sig.send(simeis_data::game::GameSignal::Tick).await else {
-        return build_response(Err(Errcode::GameSignalSend));
-    };
-    build_response(Ok(json!({})))
+async fn tick_server(srv: GameState, req: HttpRequest) -> impl web::Responder {
+    check_admin!(srv, req);
+    if let Err(e) = send_ticks(&srv, 1).await {
+        return build_response(&srv, Err(e));
+    }
+    build_response(&srv, Ok(json!({})))
 }
 
-#[cfg(feature = "testing")]
 #[web::get("/tick/{n}")]
-async fn tick_server_n(srv: GameState, n: Path<usize>) -> impl web::Responder {
-    let n = n.as_ref().clone();
+async fn tick_server_n(srv: GameState, n: Path<usize>, req: HttpRequest) -> impl web::Responder {
+    check_admin!(srv, req);
+    if *n > MAX_TICKS_PER_REQUEST {
+        return build_response(&srv, Err(Errcode::InvalidArgument("n")));
+    }
+    if let Err(e) = send_ticks(&srv, *n).await {
+        return build_response(&srv, Err(e));
+    }
+    build_response(&srv, Ok(json!({})))
+}
+
+async fn send_ticks(srv: &Game, n: usize) -> Result<(), Errcode> {
+    if !srv.clock.is_manual() {
+        return Err(Errcode::ClockNotManual);
+    }
+    if srv.clock.is_paused() {
+        return Err(Errcode::GamePaused);
+    }
     for _ in 0..n {
-        let Ok(_) = srv.send_sig.send(simeis_data::game::GameSignal::Tick).await else {
-            return build_response(Err(Errcode::GameSignalSend));
+        let Ok(_) = srv.send_sig.send(GameSignal::Tick).await else {
+            return Err(Errcode::GameSignalSend);
         };
     }
-    build_response(Ok(json!({})))
+    Ok(())
+}
+
+fn is_admin(srv: &Game, req: &HttpRequest) -> bool {
+    for q in req.query_string().split("&") {
+        if let Some(token) = q.strip_prefix("admin=") {
+            let Ok(token) = urlencoding::decode(token) else {
+                return false;
+            };
+            return srv.config.is_admin_token(&token);
+        }
+    }
+    false
+}
+
+fn clock_status(srv: &Game) -> Value {
+    json!({
+        "period-ms": srv.clock.period().as_millis() as u64,
+        "time-scale": srv.clock.time_scale(),
+        "paused": srv.clock.is_paused(),
+        "manual": srv.clock.is_manual(),
+    })
+}
+
+#[web::get("/admin/clock")]
+async fn admin_clock(srv: GameState, req: HttpRequest) -> impl web::Responder {
+    check_admin!(srv, req);
+    build_response(&srv, Ok(clock_status(&srv)))
+}
+
+#[web::get("/admin/clock/manual/{manual}")]
+async fn admin_clock_manual(
+    srv: GameState,
+    manual: Path<bool>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    check_admin!(srv, req);
+    srv.clock.set_manual(*manual);
+    build_response(&srv, Ok(clock_status(&srv)))
+}
+
+#[web::get("/admin/{action}")]
+async fn admin_signal(
+    srv: GameState,
+    action: Path<String>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    check_admin!(srv, req);
+    let sig = match action.as_str() {
+        "pause" => GameSignal::Pause,
+        "resume" => GameSignal::Resume,
+        "export" => GameSignal::Export,
+        _ => return build_response(&srv, Err(Errcode::InvalidArgument("action"))),
+    };
+    let Ok(_) = srv.send_sig.send(sig).await else {
+        return build_response(&srv, Err(Errcode::GameSignalSend));
+    };
+    build_response(&srv, Ok(json!({})))
+}
+
+#[web::get("/admin/players")]
+async fn admin_list_players(srv: GameState, req: HttpRequest) -> impl web::Responder {
+    check_admin!(srv, req);
+    let players = srv.players.read().await;
+    let mut data = vec![];
+    for player in players.values() {
+        data.push(player.read().await.to_json(true));
+    }
+    build_response(&srv, Ok(json!({ "players": data })))
+}
+
+#[web::get("/admin/player/{id}")]
+async fn admin_get_player(
+    srv: GameState,
+    id: Path<PlayerId>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    check_admin!(srv, req);
+    let players = srv.players.read().await;
+    let Some(player) = players.get(id.as_ref()) else {
+        return build_response(&srv, Err(Errcode::PlayerNotFound(*id)));
+    };
+    let player = player.read().await;
+    build_response(&srv, Ok(player.to_json(true)))
+}
+
+#[web::get("/admin/player/{id}/kick")]
+async fn admin_kick_player(
+    srv: GameState,
+    id: Path<PlayerId>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    check_admin!(srv, req);
+    build_response(&srv, srv.kick_player(*id).await.map(|_| json!({})))
+}
+
+#[web::get("/admin/player/{id}/money/{amnt}")]
+async fn admin_grant_money(
+    srv: GameState,
+    args: Path<(PlayerId, f64)>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    check_admin!(srv, req);
+    let (id, amnt) = args.as_ref();
+    if !amnt.is_finite() {
+        return build_response(&srv, Err(Errcode::InvalidArgument("amnt")));
+    }
+    let players = srv.players.read().await;
+    let Some(player) = players.get(id) else {
+        return build_response(&srv, Err(Errcode::PlayerNotFound(*id)));
+    };
+    let mut player = player.write().await;
+    player.money += amnt;
+    build_response(&srv, Ok(json!({ "money": player.money })))
+}
+
+#[web::get("/admin/market/{resource}/price/{price}")]
+async fn admin_set_price(
+    srv: GameState,
+    args: Path<(String, f64)>,
+    req: HttpRequest,
+) -> impl web::Responder {
+    check_admin!(srv, req);
+    let (resource, price) = args.as_ref();
+    let Ok(resource) = Resource::from_str(resource) else {
+        return build_response(&srv, Err(Errcode::InvalidArgument("resource")));
+    };
+    if !price.is_finite() || *price <= 0.0 {
+        return build_response(&srv, Err(Errcode::InvalidArgument("price")));
+    }
+    let mut market = srv.market.write().await;
+    market.prices.insert(resource, *price);
+    build_response(&srv, Ok(to_value(&market.prices).unwrap()))
 }
 
 // CHECKED
 #[web::get("/resources")]
-async fn resources_info() -> impl web::Responder {
+async fn resources_info(srv: GameState) -> impl web::Responder {
     let mut data = BTreeMap::new();
     for res in Resource::iter() {
         if res.mineable(u8::MAX) || res.suckable(u8::MAX) {
@@ -1023,7 +1633,7 @@ async fn resources_info() -> impl web::Responder {
             );
         }
     }
-    build_response(Ok(to_value(data).unwrap()))
+    build_response(&srv, Ok(to_value(data).unwrap()))
 }
 
 // CHECKED
@@ -1031,27 +1641,10 @@ async fn resources_info() -> impl web::Responder {
 async fn gamestats(srv: GameState) -> impl web::Responder {
     let mut data = BTreeMap::new();
     let all_players = srv.players.read().await;
-    let mut players = vec![];
     for (id, player) in all_players.iter() {
-        players.push((id, player.read().await));
-    }
-    let galaxy = srv.galaxy.read().await;
-
-    for (id, p) in players {
-        let potential = {
-            let mut s = 0.0;
-            for (_, coord) in p.stations.iter() {
-                let sta = galaxy.get_station(coord).await.unwrap();
-                let station = sta.read().await;
-                s += station
-                    .cargo
-                    .resources
-                    .iter()
-                    .map(|(r, amnt)| r.base_price() * amnt)
-                    .sum::<f64>();
-            }
-            s
-        };
+        let p = player.read().await;
+        // What the player owns, apart from its money
+        let potential = p.scores.last().map(|s| s.fleet + s.cargo).unwrap_or(0.0);
 
         data.insert(
             id,
@@ -1066,56 +1659,260 @@ async fn gamestats(srv: GameState) -> impl web::Responder {
             }),
         );
     }
-    build_response(Ok(to_value(data).unwrap()))
+    build_response(&srv, Ok(to_value(data).unwrap()))
+}
+
+#[web::get("/leaderboard")]
+async fn get_leaderboard(srv: GameState) -> impl web::Responder {
+    let all_players = srv.players.read().await;
+    let mut entries = vec![];
+    for player in all_players.values() {
+        let player = player.read().await;
+        if !player.lost {
+            entries.push(LeaderboardEntry::new(&player));
+        }
+    }
+    drop(all_players);
+    build_response(&srv, Ok(leaderboard(entries)))
+}
+
+#[web::get("/metrics")]
+async fn get_metrics(
+    srv: GameState,
+    reqs: web::types::State<Arc<RequestMetrics>>,
+) -> impl web::Responder {
+    let mut out = MetricsWriter::default();
+    srv.render_metrics(&mut out).await;
+    reqs.render(&mut out);
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(out.finish())
 }
 
 #[web::get("/version")]
-async fn get_version() -> impl web::Responder {
+async fn get_version(srv: GameState) -> impl web::Responder {
     let v = env!("CARGO_PKG_VERSION");
-    build_response(Ok(json!({"version": v})))
+    build_response(&srv, Ok(json!({"version": v})))
 }
 
 pub fn configure(srv: &mut ServiceConfig) {
-    #[cfg(feature = "testing")]
-    srv.service(tick_server).service(tick_server_n);
-
     srv.service(ping)
+        .service(tick_server)
+        .service(tick_server_n)
+        .service(admin_clock)
+        .service(admin_clock_manual)
+        .service(admin_list_players)
+        .service(admin_get_player)
+        .service(admin_kick_player)
+        .service(admin_grant_money)
+        .service(admin_set_price)
+        .service(admin_signal)
         .service(get_version)
+        .service(get_metrics)
         .service(gamestats)
+        .service(get_leaderboard)
         .service(resources_info)
         .service(get_syslogs)
         .service(hire_crew)
+        .service(list_crew_candidates)
+        .service(hire_crew_candidate)
         .service(get_crew_upgrades)
         .service(buy_crew_upgrade)
         .service(upgrade_station_trader)
         .service(assign_pilot)
         .service(assign_operator)
         .service(assign_trader)
+        .service(unassign_trader)
+        .service(unassign_crew)
+        .service(transfer_crew)
+        .service(fire_crew)
         .service(scan)
         .service(compute_travel_costs)
         .service(get_ship_status)
         .service(ask_navigate)
         .service(stop_navigation)
+        .service(ship_scan)
         .service(shipyard_buy_ship)
         .service(list_shipyard_ships)
+        .service(shipyard_sell_ship)
+        .service(shipyard_scrap_ship)
         .service(shipyard_buy_upgrade)
         .service(shipyard_list_upgrades)
         .service(buy_ship_module)
+        .service(sell_ship_module)
         .service(get_ship_module_upgrade_prices)
         .service(buy_ship_module_upgrade)
         .service(get_prices_ship_module)
         .service(start_extraction)
         .service(stop_extraction)
         .service(unload_ship_cargo)
+        .service(refuel_ship_from_cargo)
+        .service(start_ship_repair)
+        .service(stop_ship_repair)
+        .service(get_tow_cost)
+        .service(call_tow)
+        .service(transfer_ship_fuel)
+        .service(transfer_ship_cargo)
         .service(get_station_status)
         .service(get_station_upgrades)
         .service(buy_station_cargo)
+        .service(buy_station_scanner_upgrade)
         .service(refuel_ship)
         .service(repair_ship)
         .service(get_fee_rate)
         .service(get_market_prices)
+        .service(get_inflation)
         .service(buy_resource)
         .service(sell_resource)
+        .service(get_player_map)
+        .service(get_player_scores)
+        .service(get_player_achievements)
         .service(get_player)
         .service(new_player);
 }
+
+// Many players calling the API at the same time, to catch a wrong lock order in a handler
+#[ntex::test]
+async fn test_concurrent_players_no_deadlock() {
+    use ntex::util::join_all;
+    use ntex::web::{test, App};
+    use simeis_data::config::GameConfig;
+
+    const NB_PLAYERS: usize = 32;
+    const NB_ITER: usize = 50;
+    const ADMIN: &str = "admin";
+
+    let config = GameConfig {
+        admin_token: Some(ADMIN.to_string()),
+        ..GameConfig::default()
+    };
+    let (handle, game) = Game::init(config);
+    let app = test::init_service(
+        App::new()
+            .state(game.clone())
+            .state(Arc::new(RequestMetrics::default()))
+            .configure(configure),
+    )
+    .await;
+
+    let call_raw = |url: String| {
+        let app = &app;
+        async move {
+            let req = test::TestRequest::with_uri(&url).to_request();
+            test::read_response(app, req).await
+        }
+    };
+    // The calls must succeed, unless they fail with one of the allowed error types
+    let call = |url: String, allowed: &'static [&'static str]| async move {
+        let body = call_raw(url.clone()).await;
+        let res = serde_json::from_slice::<Value>(&body).unwrap_or_else(|e| panic!("{url}: {e}"));
+        if res["error"] != "ok" {
+            let errtype = res["type"].as_str().unwrap_or_default();
+            let errtype = errtype.split('(').next().unwrap();
+            assert!(allowed.contains(&errtype), "{url}: {res}");
+        }
+        res
+    };
+    const NO_MONEY: &[&str] = &["NotEnoughMoney"];
+    // The trader of the station may not be hired yet
+    const MARKET: &[&str] = &["NotEnoughMoney", "NoTraderAssigned", "SellNothing"];
+
+    let player_session = |n: usize| async move {
+        let res = call(format!("/player/new/stress-{n}"), &[]).await;
+        let pid = res["playerId"].as_u64().unwrap();
+        let key = urlencoding::encode(res["key"].as_str().unwrap()).into_owned();
+        // Enough money to pay the wages of the whole crew until the end
+        call(format!("/admin/player/{pid}/money/1e12?admin={ADMIN}"), &[]).await;
+        let res = call(format!("/player/{pid}?key={key}"), &[]).await;
+        let sid = res["stations"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .next()
+            .unwrap()
+            .clone();
+
+        for i in 0..NB_ITER {
+            let st = format!("/station/{sid}");
+            let urls: Vec<(String, &[&str])> = match (n + i) % 8 {
+                0 => {
+                    let res = call(format!("{st}/shipyard/list?key={key}"), &[]).await;
+                    let ship = res["ships"][0]["id"].as_u64().unwrap();
+                    vec![(format!("{st}/shipyard/buy/{ship}?key={key}"), NO_MONEY)]
+                }
+                1 => {
+                    let res = call(format!("{st}/crew/hire/trader?key={key}"), NO_MONEY).await;
+                    let crew = res["id"].as_u64();
+                    crew.map(|crew| {
+                        let url = format!("{st}/crew/assign/{crew}/trading?key={key}");
+                        (url, &["CrewNotNeeded"][..])
+                    })
+                    .into_iter()
+                    .collect()
+                }
+                2 => {
+                    let res = call(format!("{st}/crew/hire/pilot?key={key}"), NO_MONEY).await;
+                    let crew = res["id"].as_u64();
+                    let res = call(format!("/player/{pid}?key={key}"), &[]).await;
+                    match (crew, res["ships"][0]["id"].as_u64()) {
+                        (Some(crew), Some(ship)) => vec![
+                            (
+                                format!("{st}/crew/assign/{crew}/{ship}/pilot?key={key}"),
+                                &["CrewNotNeeded", "ShipNotInStation"][..],
+                            ),
+                            (format!("/ship/{ship}/scan?key={key}"), &["NoScanner"]),
+                            (
+                                format!("/ship/{ship}/navigate/1/1/1?key={key}"),
+                                &["ShipNotIdle", "NullDistance", "CannotPerformTravel"],
+                            ),
+                        ],
+                        _ => vec![],
+                    }
+                }
+                3 => vec![
+                    (format!("{st}/scan?key={key}"), &[]),
+                    (format!("{st}?key={key}"), &[]),
+                    (format!("/player/map?key={key}"), &[]),
+                ],
+                4 => vec![
+                    (format!("/market/{sid}/buy/Stone/1?key={key}"), MARKET),
+                    (format!("/market/{sid}/sell/Stone/1?key={key}"), MARKET),
+                    (format!("/market/{sid}/fee_rate?key={key}"), MARKET),
+                ],
+                5 => {
+                    let metrics = call_raw("/metrics".to_string()).await;
+                    assert!(!metrics.is_empty());
+                    vec![
+                        ("/gamestats".to_string(), &[]),
+                        ("/leaderboard".to_string(), &[]),
+                    ]
+                }
+                6 => vec![
+                    (format!("/admin/players?admin={ADMIN}"), &[]),
+                    (format!("/admin/player/{pid}/money/10?admin={ADMIN}"), &[]),
+                ],
+                _ => vec![
+                    (format!("/syslogs?key={key}"), &[]),
+                    (format!("/player/scores?key={key}"), &[]),
+                    (format!("/player/achievements?key={key}"), &[]),
+                ],
+            };
+            for (url, allowed) in urls {
+                call(url, allowed).await;
+            }
+        }
+    };
+
+    let all = join_all((0..NB_PLAYERS).map(player_session));
+    let res = ntex::time::timeout(ntex::time::Seconds(120), all).await;
+    assert!(res.is_ok(), "Deadlock between the players");
+    let players = game.players.read().await;
+    assert_eq!(players.len(), NB_PLAYERS);
+    for player in players.values() {
+        let player = player.read().await;
+        assert!(!player.lost);
+        assert!(!player.ships.is_empty());
+    }
+    drop(players);
+    game.stop(handle).await;
+}
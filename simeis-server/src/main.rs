@@ -1,9 +1,12 @@
 #![allow(unexpected_cfgs)]
 use ntex::web;
+use std::sync::Arc;
 
+use simeis_data::config::GameConfig;
 use simeis_data::game::Game;
 
 mod api;
+mod metrics;
 
 pub type GameState = ntex::web::types::State<Game>;
 
@@ -25,13 +28,16 @@ async fn main() -> std::io::Result<()> {
         .init();
 
     log::info!("Running on http://0.0.0.0:{port}");
-    let (gamethread, state) = Game::init();
+    let (gamethread, state) = Game::init(GameConfig::from_env());
     let game = state.clone();
+    let reqmetrics = Arc::new(metrics::RequestMetrics::default());
 
     let res = web::HttpServer::new(move || {
         web::App::new()
             .wrap(web::middleware::Logger::default())
+            .wrap(metrics::Metrics(reqmetrics.clone()))
             .state(state.clone())
+            .state(reqmetrics.clone())
             .configure(api::configure)
     })
     .stop_runtime()
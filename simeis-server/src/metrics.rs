@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use ntex::http::StatusCode;
+use ntex::service::{Middleware, Service, ServiceCtx};
+use ntex::web::{WebRequest, WebResponse};
+use simeis_data::metrics::MetricsWriter;
+
+// Number of requests and total time spent, per route
+#[derive(Default)]
+pub struct RequestMetrics {
+    routes: Mutex<BTreeMap<String, (u64, f64)>>,
+}
+
+impl RequestMetrics {
+    fn record(&self, route: String, took: f64) {
+        let mut routes = self.routes.lock().unwrap();
+        let stats = routes.entry(route).or_default();
+        stats.0 += 1;
+        stats.1 += took;
+    }
+
+    pub fn render(&self, out: &mut MetricsWriter) {
+        let routes = self.routes.lock().unwrap().clone();
+        out.header(
+            "simeis_http_requests_total",
+            "counter",
+            "Requests received per route",
+        );
+        for (route, (nb, _)) in routes.iter() {
+            out.value(
+                "simeis_http_requests_total",
+                &[("route", route)],
+                *nb as f64,
+            );
+        }
+        out.header(
+            "simeis_http_request_duration_seconds",
+            "summary",
+            "Time spent answering requests per route",
+        );
+        for (route, (nb, took)) in routes.iter() {
+            let labels = [("route", route.as_str())];
+            out.value("simeis_http_request_duration_seconds_sum", &labels, *took);
+            out.value(
+                "simeis_http_request_duration_seconds_count",
+                &labels,
+                *nb as f64,
+            );
+        }
+    }
+}
+
+// Requests that didn't match any route share a single entry
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+// Rebuilds the pattern of the route that matched, from the parameters captured by the router,
+// so each route has a single entry whatever the IDs and names in the URL
+fn route_of(res: &WebResponse) -> String {
+    if res.status() == StatusCode::NOT_FOUND {
+        return UNMATCHED_ROUTE.to_string();
+    }
+    let req = res.request();
+    let mut params = req.match_info().iter().peekable();
+    let mut route = String::new();
+    for part in req.path().split('/').filter(|p| !p.is_empty()) {
+        route.push('/');
+        // The router gives the parameters percent-decoded, the route literals never are
+        match params.next_if(|(_, value)| *value == part || part.contains('%')) {
+            Some((name, _)) => {
+                route.push('{');
+                route.push_str(name);
+                route.push('}');
+            }
+            None => route.push_str(part),
+        }
+    }
+    if route.is_empty() {
+        route.push('/');
+    }
+    route
+}
+
+pub struct Metrics(pub Arc<RequestMetrics>);
+
+impl<S> Middleware<S> for Metrics {
+    type Service = MetricsMiddleware<S>;
+
+    fn create(&self, service: S) -> Self::Service {
+        MetricsMiddleware {
+            service,
+            metrics: self.0.clone(),
+        }
+    }
+}
+
+pub struct MetricsMiddleware<S> {
+    service: S,
+    metrics: Arc<RequestMetrics>,
+}
+
+impl<S, E> Service<WebRequest<E>> for MetricsMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+
+    ntex::forward_ready!(service);
+    ntex::forward_shutdown!(service);
+
+    async fn call(
+        &self,
+        req: WebRequest<E>,
+        ctx: ServiceCtx<'_, Self>,
+    ) -> Result<Self::Response, Self::Error> {
+        let start = Instant::now();
+        let res = ctx.call(&self.service, req).await;
+        let route = match res {
+            Ok(ref res) => route_of(res),
+            Err(_) => UNMATCHED_ROUTE.to_string(),
+        };
+        self.metrics.record(route, start.elapsed().as_secs_f64());
+        res
+    }
+}